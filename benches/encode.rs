@@ -0,0 +1,124 @@
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use optimus::optimus::MAX_INT;
+use optimus::transform::Transform;
+use optimus::{Feistel, Optimus};
+
+const BATCH: u64 = 1024;
+
+/// Reference format-preserving obfuscator over the same 31 bit id space: a 16/16 bit
+/// Feistel network with AES-128 as the round function, cycle-walking any value above
+/// `MAX_INT` back into range.
+struct AesFpe {
+    cipher: Aes128,
+}
+
+impl AesFpe {
+    const ROUNDS: u8 = 4;
+
+    fn new(key: [u8; 16]) -> Self {
+        Self {
+            cipher: Aes128::new(&GenericArray::from(key)),
+        }
+    }
+
+    fn round(&self, round: u8, half: u16) -> u16 {
+        let mut block = [0u8; 16];
+        block[0] = round;
+        block[1..3].copy_from_slice(&half.to_be_bytes());
+        let mut block = GenericArray::from(block);
+        self.cipher.encrypt_block(&mut block);
+        u16::from_be_bytes([block[0], block[1]])
+    }
+
+    fn permute(&self, n: u32) -> u32 {
+        let (mut l, mut r) = ((n >> 16) as u16, n as u16);
+        for i in 0..Self::ROUNDS {
+            (l, r) = (r, l ^ self.round(i, r));
+        }
+        (u32::from(l) << 16) | u32::from(r)
+    }
+
+    fn unpermute(&self, n: u32) -> u32 {
+        let (mut l, mut r) = ((n >> 16) as u16, n as u16);
+        for i in (0..Self::ROUNDS).rev() {
+            (l, r) = (r ^ self.round(i, l), l);
+        }
+        (u32::from(l) << 16) | u32::from(r)
+    }
+
+    fn encode(&self, n: u64) -> u64 {
+        let mut v = self.permute(n as u32);
+        while u64::from(v) > MAX_INT {
+            v = self.permute(v);
+        }
+        u64::from(v)
+    }
+
+    fn decode(&self, n: u64) -> u64 {
+        let mut v = self.unpermute(n as u32);
+        while u64::from(v) > MAX_INT {
+            v = self.unpermute(v);
+        }
+        u64::from(v)
+    }
+}
+
+fn bench_single(c: &mut Criterion) {
+    let opt = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+    let fpe = AesFpe::new(*b"optimus-rs bench");
+    assert_eq!(fpe.decode(fpe.encode(15)), 15);
+    let feistel = Feistel::new(*b"optimus-rs bench", 8).unwrap();
+
+    let mut group = c.benchmark_group("single");
+    group.bench_function("optimus_encode", |b| b.iter(|| opt.encode(black_box(15))));
+    group.bench_function("optimus_decode", |b| {
+        b.iter(|| opt.decode(black_box(1103647397)))
+    });
+    group.bench_function("aes_fpe_encode", |b| b.iter(|| fpe.encode(black_box(15))));
+    group.bench_function("aes_fpe_decode", |b| {
+        b.iter(|| fpe.decode(black_box(1103647397)))
+    });
+    group.bench_function("feistel_encode", |b| {
+        b.iter(|| feistel.encode(black_box(15)))
+    });
+    group.bench_function("feistel_decode", |b| {
+        b.iter(|| feistel.decode(black_box(1103647397)))
+    });
+    group.finish();
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let opt = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+    let fpe = AesFpe::new(*b"optimus-rs bench");
+    let ids: Vec<u64> = (0..BATCH).map(|i| i * (MAX_INT / BATCH)).collect();
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(criterion::Throughput::Elements(BATCH));
+    group.bench_function("optimus_encode", |b| {
+        b.iter_batched(
+            || ids.clone(),
+            |ids| ids.into_iter().map(|n| opt.encode(n)).collect::<Vec<_>>(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("optimus_decode", |b| {
+        b.iter_batched(
+            || ids.clone(),
+            |ids| ids.into_iter().map(|n| opt.decode(n)).collect::<Vec<_>>(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("aes_fpe_encode", |b| {
+        b.iter_batched(
+            || ids.clone(),
+            |ids| ids.into_iter().map(|n| fpe.encode(n)).collect::<Vec<_>>(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_single, bench_batch);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+//! Prints a markdown table of sample ids, their encoded values and base62 strings for a
+//! config, so documentation examples can be regenerated when the algorithm changes.
+//!
+//! cargo run --example verification_table [prime random]
+use optimus::Optimus;
+
+pub fn main() {
+    let args: Vec<u64> = std::env::args()
+        .skip(1)
+        .map(|a| a.parse().expect("arguments must be integers"))
+        .collect();
+    let opt = match args[..] {
+        [prime, random] => Optimus::new_calculated(prime, random).unwrap(),
+        _ => Optimus::new(1580030173, 59260789, 1163945558).unwrap(),
+    };
+    println!(
+        "prime = {}, mod_inverse = {}, random = {}\n",
+        opt.prime(),
+        opt.mod_inverse(),
+        opt.random()
+    );
+    println!("| id | encoded | base62 |");
+    println!("|---:|--------:|-------:|");
+    let max = Optimus::MAX_INT;
+    for id in [0, 1, 2, 15, 100, 1_000, 1_000_000, max - 1, max] {
+        let encoded = opt.encode(id);
+        let base62 = opt.encode_base62(id);
+        assert_eq!(opt.decode(encoded), id);
+        assert_eq!(opt.decode_base62(&base62).unwrap(), id);
+        println!("| {id} | {encoded} | {base62} |");
+    }
+}
@@ -0,0 +1,144 @@
+//! Tools for checking how much a deployment gives away, intended for education and for
+//! testing your own configs, not for attacking other people's.
+//!
+//! Optimus is linear over the integers modulo 2^31, so a handful of known
+//! (id, encoded) pairs, e.g. from a public profile page whose numeric id is also visible
+//! elsewhere, are enough to recover the whole config. See `Optimus::security_note`.
+
+use crate::error::OptimusError;
+use crate::optimus::{check_range, inverse_pow2, Optimus, MAX_INT};
+
+impl Optimus {
+    ///Recovers a lost config from known (id, encoded) pairs, e.g. collected from logs, with
+    ///`recover_params`. At least one even and one odd id are needed.
+    ///
+    ///```
+    ///use optimus::Optimus;
+    ///let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+    ///let pairs = [(15, o.encode(15)), (1000, o.encode(1000))];
+    ///assert_eq!(Optimus::from_known_pairs(&pairs).unwrap(), o);
+    ///```
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InsufficientData` if the ids do not include both an even
+    /// and an odd one, `OptimusError::InputOutOfRange` if an encoded value is greater than
+    /// `MAX_INT`, or `OptimusError::InvalidEncoding` if no single config produces every pair
+    pub fn from_known_pairs(pairs: &[(u64, u64)]) -> Result<Self, OptimusError> {
+        for &(_, e) in pairs {
+            check_range(e)?;
+        }
+        let has_parity = |parity| pairs.iter().any(|&(n, _)| n & 1 == parity);
+        if !has_parity(0) || !has_parity(1) {
+            return Err(OptimusError::InsufficientData);
+        }
+        recover_params(pairs).ok_or(OptimusError::InvalidEncoding)
+    }
+}
+
+///Returns a config that encodes every id the way the config that produced `pairs` did,
+///or `None` if the pairs do not determine one.
+///
+///The config is solved one bit at a time from the lowest bit up: bit k of an encoded value
+///only depends on bits 0 to k of the id, prime and random, and once the lower bits of prime
+///are known the only unknowns left at bit k are bit k of prime and bit k of random. The
+///pairs determine a config when they contain at least one even and one odd id, which is
+///why `Optimus::pairs_to_break` is 2.
+///
+///The recovered prime is the original prime modulo 2^31, so the result is equal to the
+///original config whenever its prime is no greater than `MAX_INT`.
+///
+///Returns `None` if fewer than two pairs are given, if all ids have the same parity, or if
+///the pairs are inconsistent with any single config.
+///
+///```
+///use optimus::analysis::recover_params;
+///use optimus::Optimus;
+///let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+///let pairs = [(15, o.encode(15)), (1000, o.encode(1000))];
+///assert_eq!(recover_params(&pairs), Some(o));
+///```
+#[must_use]
+pub fn recover_params(pairs: &[(u64, u64)]) -> Option<Optimus> {
+    if pairs.iter().any(|&(_, e)| e > MAX_INT) {
+        return None;
+    }
+    let mut prime = 0u64;
+    let mut random = 0u64;
+    for k in 0..MAX_INT.count_ones() {
+        let mut found = None;
+        for (p_k, r_k) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            let p = prime | (p_k << k);
+            let consistent = pairs
+                .iter()
+                .all(|&(n, e)| ((n.wrapping_mul(p) ^ (r_k << k) ^ e) >> k) & 1 == 0);
+            if consistent {
+                if found.is_some() {
+                    return None;
+                }
+                found = Some((p_k, r_k));
+            }
+        }
+        let (p_k, r_k) = found?;
+        prime |= p_k << k;
+        random |= r_k << k;
+    }
+    if prime & 1 == 0 {
+        return None;
+    }
+    Some(Optimus::new_unchecked(
+        prime,
+        inverse_pow2(prime) & MAX_INT,
+        random,
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_params() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let pairs: Vec<(u64, u64)> = [8, 15, 42, 1_000_000]
+            .into_iter()
+            .map(|n| (n, o.encode(n)))
+            .collect();
+        assert_eq!(recover_params(&pairs), Some(o));
+        assert_eq!(recover_params(&pairs[..2]), Some(o));
+    }
+
+    #[test]
+    fn test_recover_params_insufficient() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(recover_params(&[]), None);
+        assert_eq!(recover_params(&[(15, o.encode(15))]), None);
+        let even: Vec<(u64, u64)> = [2, 4, 100].into_iter().map(|n| (n, o.encode(n))).collect();
+        assert_eq!(recover_params(&even), None);
+        assert_eq!(recover_params(&[(0, 1), (1, 1), (2, 7)]), None);
+    }
+
+    #[test]
+    fn test_from_known_pairs() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let pairs: Vec<(u64, u64)> = (100..110).map(|n| (n, o.encode(n))).collect();
+        assert_eq!(Optimus::from_known_pairs(&pairs).unwrap(), o);
+        assert!(matches!(
+            Optimus::from_known_pairs(&pairs[..1]),
+            Err(OptimusError::InsufficientData)
+        ));
+        let even: Vec<(u64, u64)> = pairs.iter().copied().step_by(2).collect();
+        assert!(matches!(
+            Optimus::from_known_pairs(&even),
+            Err(OptimusError::InsufficientData)
+        ));
+        assert!(matches!(
+            Optimus::from_known_pairs(&[(0, 1), (1, 1), (2, 7)]),
+            Err(OptimusError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            Optimus::from_known_pairs(&[(0, 1), (1, MAX_INT + 1)]),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,452 @@
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::mix::splitmix64;
+use crate::optimus::{Optimus, MAX_INT};
+use crate::telemetry;
+use crate::transform::Transform;
+
+///The characters used by `encode_base62`, in digit order.
+pub const BASE62_ALPHABET: &[u8; 62] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+///The characters used by `encode_base36`, in digit order.
+pub const BASE36_ALPHABET: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+const DIGITS: &[u8; 10] = b"0123456789";
+
+///Separator written between ids by `encode_base62_into`. It is not in `BASE62_ALPHABET`.
+pub const BASE62_DELIMITER: char = ',';
+
+///Prefix of a token rerouted by `encode_avoiding`. It is not in `BASE62_ALPHABET`.
+pub const AVOIDING_MARKER: char = '_';
+
+impl Optimus {
+    ///Encodes n and returns the result as a base62 string using `BASE62_ALPHABET`.
+    ///Encoded ids are at most 6 characters long.
+    #[must_use]
+    pub fn encode_base62(&self, n: u64) -> String {
+        encode_base62(self, n)
+    }
+    ///Decodes a string produced by `encode_base62` back to the original id.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Parse` with `ParseErrorKind::EmptyInput` if s is empty or
+    /// `ParseErrorKind::OutOfAlphabet` if it contains a character outside `BASE62_ALPHABET`,
+    /// or `OptimusError::InputOutOfRange` if it represents a value above
+    /// `MAX_INT`
+    pub fn decode_base62(&self, s: &str) -> Result<u64, OptimusError> {
+        decode_base62(self, s)
+    }
+    ///Encodes n and returns the result as a lowercase base36 string using
+    ///`BASE36_ALPHABET`, for channels that are not case sensitive. Encoded ids are at most
+    ///6 characters long.
+    #[must_use]
+    pub fn encode_base36(&self, n: u64) -> String {
+        to_radix(BASE36_ALPHABET, self.encode(n))
+    }
+    ///Decodes a string produced by `encode_base36`, ignoring case.
+    /// # Errors
+    ///
+    /// Will return the same errors as `decode_base62`, for `BASE36_ALPHABET`
+    pub fn decode_base36(&self, s: &str) -> Result<u64, OptimusError> {
+        Ok(self.decode(from_radix(BASE36_ALPHABET, &s.to_ascii_lowercase())?))
+    }
+    ///Encodes n and returns the result in decimal, left padded with zeros to width digits,
+    ///for channels that only take digits. Encoded ids have at most 10 digits, so any width
+    ///from 10 gives fixed length output.
+    #[must_use]
+    pub fn encode_base10_padded(&self, n: u64, width: usize) -> String {
+        format!("{:0width$}", self.encode(n))
+    }
+    ///Decodes a string produced by `encode_base10_padded`, with any amount of padding.
+    /// # Errors
+    ///
+    /// Will return the same errors as `decode_base62`, for the digits 0 to 9
+    pub fn decode_base10_padded(&self, s: &str) -> Result<u64, OptimusError> {
+        Ok(self.decode(from_radix(DIGITS, s)?))
+    }
+    ///Appends the `encode_base62` string of every id to out, separated by
+    ///`BASE62_DELIMITER`, e.g. `"a,b,c"` for three ids. Nothing is written for an empty slice.
+    ///Reusing out across calls avoids allocating a `String` per id when rendering lists.
+    pub fn encode_base62_into(&self, ids: &[u64], out: &mut String) {
+        encode_base62_into(self, ids, out);
+    }
+    ///Decodes a list written by `encode_base62_into`. An empty string decodes to an empty
+    ///list.
+    /// # Errors
+    ///
+    /// Will return the error `decode_base62` returns for the first invalid entry, including
+    /// `ParseErrorKind::EmptyInput` for an empty entry such as in `"a,,b"`
+    pub fn decode_base62_slice(&self, s: &str) -> Result<Vec<u64>, OptimusError> {
+        decode_base62_slice(self, s)
+    }
+    ///Returns the ids as one `BASE62_DELIMITER` separated base62 string, e.g. to carry a list
+    ///of ids in a single URL path segment. An empty slice gives an empty string.
+    #[must_use]
+    pub fn encode_csv(&self, ids: &[u64]) -> String {
+        let mut out = String::new();
+        self.encode_base62_into(ids, &mut out);
+        out
+    }
+    ///Decodes a list written by `encode_csv`. Unlike `decode_base62_slice` a single trailing
+    ///delimiter is accepted, as in `"a,b,"`, so hand built lists decode too.
+    /// # Errors
+    ///
+    /// Will return the error `decode_base62` returns for the first invalid entry, including
+    /// `ParseErrorKind::EmptyInput` for an empty entry such as in `"a,,b"`
+    pub fn decode_csv(&self, s: &str) -> Result<Vec<u64>, OptimusError> {
+        self.decode_base62_slice(s.strip_suffix(BASE62_DELIMITER).unwrap_or(s))
+    }
+    ///Returns how many distinct ids fit in width base62 characters, `62^width` capped at
+    ///the size of the id space, `MAX_INT + 1`. Every id fits once this reaches
+    ///`MAX_INT + 1`, which it does from a width of 6.
+    #[must_use]
+    pub fn capacity_for_width(width: usize) -> u64 {
+        let radix = BASE62_ALPHABET.len() as u64;
+        (0..width)
+            .try_fold(1u64, |acc, _| Some(acc * radix).filter(|&c| c <= MAX_INT))
+            .unwrap_or(MAX_INT + 1)
+    }
+    ///Encodes n like `encode_base62`, but with `BASE62_ALPHABET` shuffled by a permutation
+    ///derived from random, so deployments with different randoms produce different looking
+    ///strings even where their encoded values coincide. The permutation never changes
+    ///between releases.
+    ///
+    ///CAUTION: the shuffled alphabet is derived from random and is as secret as the config.
+    #[must_use]
+    pub fn encode_shuffled_base62(&self, n: u64) -> String {
+        to_radix(&self.shuffled_alphabet(), self.encode(n))
+    }
+    ///Decodes a string produced by `encode_shuffled_base62` back to the original id.
+    /// # Errors
+    ///
+    /// Will return the same errors as `decode_base62`
+    pub fn decode_shuffled_base62(&self, s: &str) -> Result<u64, OptimusError> {
+        Ok(self.decode(from_radix(&self.shuffled_alphabet(), s)?))
+    }
+    ///Returns `BASE62_ALPHABET` shuffled with Fisher-Yates, drawing from `SplitMix64`
+    ///seeded with random.
+    fn shuffled_alphabet(&self) -> [u8; 62] {
+        let mut alphabet = *BASE62_ALPHABET;
+        let mut state = self.random;
+        for i in (1..alphabet.len()).rev() {
+            let j = splitmix64(&mut state) % (i as u64 + 1);
+            alphabet.swap(i, usize::try_from(j).unwrap_or_default());
+        }
+        alphabet
+    }
+    ///Encodes n like `encode_base62`, but reroutes tokens that contain a blocked word, so
+    ///ids never spell something offensive or reserved. Words are matched
+    ///case-insensitively and empty words are ignored.
+    ///
+    ///A rerouted token is `AVOIDING_MARKER`, then an attempt digit k from 1 to 61, then the
+    ///base62 of the encoded id xored with a fixed tweak derived from k. The first k whose
+    ///whole token is clean is used. The tweaks never change between releases and do not
+    ///depend on blocklist, so `decode_avoiding` undoes a token without knowing the
+    ///blocklist it was made with, and the same blocklist always gives the same token.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::NoCleanEncoding` if every attempt contains a blocked word
+    pub fn encode_avoiding(&self, n: u64, blocklist: &[&str]) -> Result<String, OptimusError> {
+        let blocklist: Vec<String> = blocklist
+            .iter()
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_ascii_lowercase())
+            .collect();
+        let is_clean = |token: &str| {
+            let token = token.to_ascii_lowercase();
+            !blocklist.iter().any(|w| token.contains(w.as_str()))
+        };
+        let e = self.encode(n);
+        let natural = to_radix(BASE62_ALPHABET, e);
+        if is_clean(&natural) {
+            return Ok(natural);
+        }
+        (1..BASE62_ALPHABET.len())
+            .map(|k| {
+                let mut token = String::from(AVOIDING_MARKER);
+                token.push(char::from(BASE62_ALPHABET[k]));
+                push_radix(BASE62_ALPHABET, e ^ avoiding_tweak(k), &mut token);
+                token
+            })
+            .find(|token| is_clean(token))
+            .ok_or(OptimusError::NoCleanEncoding)
+    }
+    ///Decodes a token produced by `encode_avoiding`, rerouted or not.
+    /// # Errors
+    ///
+    /// Will return the errors of `decode_base62`, and `OptimusError::InvalidEncoding` for a
+    /// rerouted token with attempt digit 0
+    pub fn decode_avoiding(&self, s: &str) -> Result<u64, OptimusError> {
+        let Some(rest) = s.strip_prefix(AVOIDING_MARKER) else {
+            return self.decode_base62(s);
+        };
+        let mut chars = rest.chars();
+        let c = chars
+            .next()
+            .ok_or(OptimusError::Parse(ParseErrorKind::EmptyInput))?;
+        let k = BASE62_ALPHABET
+            .iter()
+            .position(|&b| char::from(b) == c)
+            .ok_or(OptimusError::Parse(ParseErrorKind::OutOfAlphabet(c)))?;
+        if k == 0 {
+            return Err(OptimusError::InvalidEncoding);
+        }
+        let e = from_radix(BASE62_ALPHABET, chars.as_str())? ^ avoiding_tweak(k);
+        Ok(self.decode(e))
+    }
+}
+
+pub(crate) fn encode_base62<T: Transform + ?Sized>(t: &T, n: u64) -> String {
+    to_radix(BASE62_ALPHABET, t.encode(n))
+}
+
+pub(crate) fn decode_base62<T: Transform + ?Sized>(t: &T, s: &str) -> Result<u64, OptimusError> {
+    Ok(t.decode(from_radix(BASE62_ALPHABET, s)?))
+}
+
+pub(crate) fn encode_base62_into<T: Transform + ?Sized>(t: &T, ids: &[u64], out: &mut String) {
+    telemetry::record_batch(ids.len());
+    for (i, &id) in ids.iter().enumerate() {
+        if i > 0 {
+            out.push(BASE62_DELIMITER);
+        }
+        push_radix(BASE62_ALPHABET, t.encode(id), out);
+    }
+}
+
+pub(crate) fn decode_base62_slice<T: Transform + ?Sized>(
+    t: &T,
+    s: &str,
+) -> Result<Vec<u64>, OptimusError> {
+    if s.is_empty() {
+        return Ok(vec![]);
+    }
+    let ids = s
+        .split(BASE62_DELIMITER)
+        .map(|part| decode_base62(t, part))
+        .collect::<Result<Vec<_>, _>>()?;
+    telemetry::record_batch(ids.len());
+    Ok(ids)
+}
+
+///Returns the value `encode_avoiding` xors into attempt k, which is within `MAX_INT`.
+fn avoiding_tweak(k: usize) -> u64 {
+    let mut state = k as u64;
+    splitmix64(&mut state) & MAX_INT
+}
+
+pub(crate) fn to_radix(alphabet: &[u8], n: u64) -> String {
+    let mut s = String::new();
+    push_radix(alphabet, n, &mut s);
+    s
+}
+
+pub(crate) fn push_radix(alphabet: &[u8], mut n: u64, out: &mut String) {
+    let radix = alphabet.len() as u64;
+    let mut digits = [0u8; u64::BITS as usize];
+    let mut len = 0;
+    loop {
+        digits[len] = alphabet[usize::try_from(n % radix).unwrap_or_default()];
+        len += 1;
+        n /= radix;
+        if n == 0 {
+            break;
+        }
+    }
+    out.extend(digits[..len].iter().rev().map(|&b| char::from(b)));
+}
+
+pub(crate) fn from_radix(alphabet: &[u8], s: &str) -> Result<u64, OptimusError> {
+    if s.is_empty() {
+        return Err(OptimusError::Parse(ParseErrorKind::EmptyInput));
+    }
+    let radix = alphabet.len() as u64;
+    let value = s.chars().try_fold(0u64, |acc, c| {
+        let digit = alphabet
+            .iter()
+            .position(|&b| char::from(b) == c)
+            .ok_or(OptimusError::Parse(ParseErrorKind::OutOfAlphabet(c)))?;
+        Ok::<_, OptimusError>(acc.saturating_mul(radix).saturating_add(digit as u64))
+    })?;
+    if value > MAX_INT {
+        return Err(OptimusError::InputOutOfRange {
+            value,
+            max: MAX_INT,
+        });
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base62() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(to_radix(BASE62_ALPHABET, 0), "0");
+        assert_eq!(to_radix(BASE62_ALPHABET, 61), "z");
+        assert_eq!(to_radix(BASE62_ALPHABET, 62), "10");
+        assert_eq!(to_radix(BASE62_ALPHABET, MAX_INT), "2LKcb1");
+        for id in [0, 1, 15, MAX_INT - 1, MAX_INT] {
+            let s = o.encode_base62(id);
+            assert!(s.len() <= 6);
+            assert_eq!(o.decode_base62(&s).unwrap(), id);
+        }
+        assert!(matches!(
+            o.decode_base62(""),
+            Err(OptimusError::Parse(ParseErrorKind::EmptyInput))
+        ));
+        assert!(matches!(
+            o.decode_base62("ab-c"),
+            Err(OptimusError::Parse(ParseErrorKind::OutOfAlphabet('-')))
+        ));
+        assert!(matches!(
+            o.decode_base62("2LKcb2"),
+            Err(OptimusError::InputOutOfRange {
+                value: 2147483648,
+                ..
+            })
+        ));
+        assert!(matches!(
+            o.decode_base62("zzzzzzzzzzzzzzzzzzzz"),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_base62_slice() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let ids = [0, 1, 15, 1_000_000, MAX_INT];
+        let mut out = String::with_capacity(64);
+        o.encode_base62_into(&ids, &mut out);
+        let expected: Vec<String> = ids.iter().map(|&id| o.encode_base62(id)).collect();
+        assert_eq!(out, expected.join(","));
+        assert_eq!(o.decode_base62_slice(&out).unwrap(), ids);
+
+        out.clear();
+        o.encode_base62_into(&[], &mut out);
+        assert_eq!(out, "");
+        assert_eq!(o.decode_base62_slice(&out).unwrap(), Vec::<u64>::new());
+        assert!(matches!(
+            o.decode_base62_slice("1,,2"),
+            Err(OptimusError::Parse(ParseErrorKind::EmptyInput))
+        ));
+    }
+
+    #[test]
+    fn test_csv() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        for ids in [&[][..], &[15], &[0, 15, MAX_INT]] {
+            let s = o.encode_csv(ids);
+            assert_eq!(o.decode_csv(&s).unwrap(), ids);
+            assert_eq!(o.decode_csv(&format!("{s},")).unwrap(), ids);
+        }
+        assert_eq!(o.encode_csv(&[]), "");
+        assert_eq!(o.encode_csv(&[15]), o.encode_base62(15));
+        assert!(matches!(
+            o.decode_csv("1,,2"),
+            Err(OptimusError::Parse(ParseErrorKind::EmptyInput))
+        ));
+        assert!(matches!(
+            o.decode_csv("1,2,,"),
+            Err(OptimusError::Parse(ParseErrorKind::EmptyInput))
+        ));
+    }
+
+    #[test]
+    fn test_avoiding() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let natural = o.encode_base62(15);
+        assert_eq!(o.encode_avoiding(15, &["", "zzzz"]).unwrap(), natural);
+        assert_eq!(o.decode_avoiding(&natural).unwrap(), 15);
+        // Block part of the natural token, in a different case, to force a reroute.
+        let blocked = natural[1..4].to_ascii_uppercase();
+        let token = o.encode_avoiding(15, &[&blocked]).unwrap();
+        assert!(token.starts_with(AVOIDING_MARKER), "{token}");
+        assert!(!token
+            .to_ascii_lowercase()
+            .contains(&blocked.to_ascii_lowercase()));
+        assert_eq!(token, o.encode_avoiding(15, &[&blocked]).unwrap());
+        assert_eq!(o.decode_avoiding(&token).unwrap(), 15);
+        for id in [0, 1, MAX_INT] {
+            let token = o.encode_avoiding(id, &[&o.encode_base62(id)]).unwrap();
+            assert_eq!(o.decode_avoiding(&token).unwrap(), id);
+        }
+        // Every rerouted token contains the marker.
+        assert!(matches!(
+            o.encode_avoiding(15, &[&natural, "_"]),
+            Err(OptimusError::NoCleanEncoding)
+        ));
+        assert!(matches!(
+            o.decode_avoiding("_0abc"),
+            Err(OptimusError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            o.decode_avoiding("_"),
+            Err(OptimusError::Parse(ParseErrorKind::EmptyInput))
+        ));
+    }
+
+    #[test]
+    fn test_shuffled_base62() {
+        let a = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let b = a.with_random(42).unwrap();
+        let mut sorted = a.shuffled_alphabet();
+        sorted.sort_unstable();
+        let mut expected = *BASE62_ALPHABET;
+        expected.sort_unstable();
+        assert_eq!(sorted, expected);
+        assert_ne!(a.shuffled_alphabet(), b.shuffled_alphabet());
+        assert_ne!(a.encode_shuffled_base62(15), b.encode_shuffled_base62(15));
+        for o in [a, b] {
+            for id in [0, 1, 15, MAX_INT] {
+                let s = o.encode_shuffled_base62(id);
+                assert_eq!(o.decode_shuffled_base62(&s).unwrap(), id);
+            }
+        }
+        assert!(matches!(
+            a.decode_shuffled_base62("-"),
+            Err(OptimusError::Parse(ParseErrorKind::OutOfAlphabet('-')))
+        ));
+    }
+
+    #[test]
+    fn test_capacity_for_width() {
+        assert_eq!(Optimus::capacity_for_width(0), 1);
+        assert_eq!(Optimus::capacity_for_width(1), 62);
+        assert_eq!(Optimus::capacity_for_width(5), 916132832);
+        assert_eq!(Optimus::capacity_for_width(6), MAX_INT + 1);
+        assert_eq!(Optimus::capacity_for_width(100), MAX_INT + 1);
+    }
+
+    #[test]
+    fn test_base36_and_base10() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(to_radix(BASE36_ALPHABET, MAX_INT), "zik0zj");
+        assert_eq!(o.encode_base10_padded(15, 12), "001103647397");
+        assert_eq!(o.encode_base10_padded(15, 4), "1103647397");
+        for id in [0, 1, 15, MAX_INT] {
+            let s = o.encode_base36(id);
+            assert!(s.len() <= 6);
+            assert_eq!(o.decode_base36(&s).unwrap(), id);
+            assert_eq!(o.decode_base36(&s.to_ascii_uppercase()).unwrap(), id);
+            let s = o.encode_base10_padded(id, 10);
+            assert_eq!(s.len(), 10);
+            assert_eq!(o.decode_base10_padded(&s).unwrap(), id);
+        }
+        assert!(matches!(
+            o.decode_base36("ab-c"),
+            Err(OptimusError::Parse(ParseErrorKind::OutOfAlphabet('-')))
+        ));
+        assert!(matches!(
+            o.decode_base10_padded("12a"),
+            Err(OptimusError::Parse(ParseErrorKind::OutOfAlphabet('a')))
+        ));
+        assert!(matches!(
+            o.decode_base10_padded("2147483648"),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+    }
+}
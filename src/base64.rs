@@ -0,0 +1,88 @@
+use crate::error::OptimusError;
+use crate::optimus::Optimus;
+
+///The URL-safe base64 alphabet (RFC 4648 section 5) used by `encode_base64url`.
+pub const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+///Length of an unpadded base64 encoding of 4 bytes.
+const ENCODED_LEN: usize = 6;
+
+impl Optimus {
+    ///Encodes n and returns the 4 big-endian bytes of `encode_bytes` as unpadded URL-safe
+    ///base64, as used by JWTs. Encoded ids are always 6 characters long.
+    #[must_use]
+    pub fn encode_base64url(&self, n: u64) -> String {
+        // 32 bits in 6 characters of 6 bits, the last 4 bits are zero.
+        let bits = u64::from(u32::from_be_bytes(self.encode_bytes(n))) << 4;
+        (0..ENCODED_LEN)
+            .rev()
+            .map(|i| {
+                char::from(
+                    BASE64URL_ALPHABET[usize::try_from((bits >> (6 * i)) & 63).unwrap_or_default()],
+                )
+            })
+            .collect()
+    }
+    ///Decodes a string produced by `encode_base64url` back to the original id.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InvalidEncoding` if s is not the unpadded URL-safe base64
+    /// of 4 bytes, or
+    /// `OptimusError::InputOutOfRange` if they represent a value above `MAX_INT`
+    pub fn decode_base64url(&self, s: &str) -> Result<u64, OptimusError> {
+        if s.len() != ENCODED_LEN {
+            return Err(OptimusError::InvalidEncoding);
+        }
+        let bits = s.bytes().try_fold(0u64, |acc, c| {
+            let digit = BASE64URL_ALPHABET
+                .iter()
+                .position(|&b| b == c)
+                .ok_or(OptimusError::InvalidEncoding)?;
+            Ok::<_, OptimusError>((acc << 6) | digit as u64)
+        })?;
+        // A canonical encoding leaves the bits after the 4th byte clear.
+        if bits & 0xF != 0 {
+            return Err(OptimusError::InvalidEncoding);
+        }
+        let bytes = u32::try_from(bits >> 4)
+            .map_err(|_| OptimusError::InvalidEncoding)?
+            .to_be_bytes();
+        self.decode_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+    use crate::optimus::MAX_INT;
+
+    #[test]
+    fn test_base64url() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        // 1103647397, the encoding of 15, is 0x41C852A5.
+        assert_eq!(o.encode_base64url(15), "QchSpQ");
+        for id in [0, 1, 15, MAX_INT - 1, MAX_INT] {
+            let s = o.encode_base64url(id);
+            assert_eq!(s.len(), 6);
+            assert_eq!(o.decode_base64url(&s).unwrap(), id);
+        }
+        for invalid in ["", "QchSpQ==", "QchSp", "QchS+Q", "QchS/Q", "QchSpR"] {
+            assert!(
+                matches!(
+                    o.decode_base64url(invalid),
+                    Err(OptimusError::InvalidEncoding)
+                ),
+                "{invalid}"
+            );
+        }
+        assert!(matches!(
+            o.decode_base64url("gAAAAA"),
+            Err(OptimusError::InputOutOfRange {
+                value: 2147483648,
+                ..
+            })
+        ));
+    }
+}
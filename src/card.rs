@@ -0,0 +1,114 @@
+use std::fmt::Write;
+
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::optimus::{Optimus, ALGORITHM_VERSION};
+
+const CARD_HEADER: &str = "optimus config card";
+
+impl Optimus {
+    ///Returns a printable block for handing this config to someone else, read back with
+    ///`from_config_card`:
+    ///
+    ///```text
+    ///optimus config card
+    ///secret: 1580030173:59260789:1163945558
+    ///fingerprint: 6f0c8fc388f58dc5
+    ///algorithm: 1
+    ///```
+    ///The secret line is `to_secret_string`. The `fingerprint` in hex and the
+    ///`algorithm_version` can be read aloud to confirm the config arrived intact.
+    ///
+    ///CAUTION: the card contains the whole config, so treat it as a secret.
+    #[must_use]
+    pub fn config_card(&self) -> String {
+        format!(
+            "{CARD_HEADER}\nsecret: {}\nfingerprint: {}\nalgorithm: {}\n",
+            self.to_secret_string(),
+            fingerprint_hex(self),
+            self.algorithm_version()
+        )
+    }
+    ///Parses a card written by `config_card`, checking its fingerprint and algorithm version
+    ///against the secret. Surrounding whitespace and blank lines are ignored.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Parse` if the header or a line is missing, or the
+    /// fingerprint or algorithm version does not match, or any error of `FromStr` for the
+    /// secret line
+    pub fn from_config_card(s: &str) -> Result<Self, OptimusError> {
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+        if lines.next() != Some(CARD_HEADER) {
+            return Err(OptimusError::Parse(ParseErrorKind::InvalidSyntax(format!(
+                "missing `{CARD_HEADER}` header"
+            ))));
+        }
+        let mut field = |name: &str| {
+            lines
+                .next()
+                .and_then(|l| l.strip_prefix(name))
+                .and_then(|l| l.strip_prefix(':'))
+                .map(str::trim)
+                .ok_or_else(|| OptimusError::Parse(ParseErrorKind::MissingField(name.to_string())))
+        };
+        let optimus: Optimus = field("secret")?.parse()?;
+        if field("fingerprint")? != fingerprint_hex(&optimus) {
+            return Err(OptimusError::Parse(ParseErrorKind::InvalidSyntax(
+                "fingerprint does not match the secret".to_string(),
+            )));
+        }
+        if field("algorithm")? != ALGORITHM_VERSION.to_string() {
+            return Err(OptimusError::Parse(ParseErrorKind::InvalidSyntax(
+                "unsupported algorithm version".to_string(),
+            )));
+        }
+        Ok(optimus)
+    }
+}
+
+fn fingerprint_hex(optimus: &Optimus) -> String {
+    let mut hex = String::with_capacity(16);
+    for b in optimus.fingerprint() {
+        let _ = write!(hex, "{b:02x}");
+    }
+    hex
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_card() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let card = o.config_card();
+        assert_eq!(
+            card,
+            "optimus config card\nsecret: 1580030173:59260789:1163945558\n\
+             fingerprint: 6f0c8fc388f58dc5\nalgorithm: 1\n"
+        );
+        assert_eq!(Optimus::from_config_card(&card).unwrap(), o);
+        assert_eq!(
+            Optimus::from_config_card(&format!("\n  {}\n\n", card.replace('\n', "\n  "))).unwrap(),
+            o
+        );
+        let tampered = card.replace("1163945558", "1163945559");
+        assert!(matches!(
+            Optimus::from_config_card(&tampered),
+            Err(OptimusError::Parse(ParseErrorKind::InvalidSyntax(_)))
+        ));
+        let future = card.replace("algorithm: 1", "algorithm: 2");
+        assert!(matches!(
+            Optimus::from_config_card(&future),
+            Err(OptimusError::Parse(ParseErrorKind::InvalidSyntax(_)))
+        ));
+        assert!(matches!(
+            Optimus::from_config_card("optimus config card\nsecret: 1580030173:59260789:1163945558"),
+            Err(OptimusError::Parse(ParseErrorKind::MissingField(f))) if f == "fingerprint"
+        ));
+        assert!(matches!(
+            Optimus::from_config_card(&o.to_secret_string()),
+            Err(OptimusError::Parse(ParseErrorKind::InvalidSyntax(_)))
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+//! Method names used by other optimus style crates, to ease switching dependencies.
+//!
+//! ```
+//! use optimus::compat::OptimusIdCompat;
+//! use optimus::Optimus;
+//! let opt = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+//! assert_eq!(opt.decode_id(opt.encode_id(15)), 15);
+//! ```
+//!
+//! Only the names are mapped. The algorithm is always this crate's: multiply by prime,
+//! mask with `MAX_INT` (2^31 - 1) and xor with random, as in the Go and PHP optimus
+//! libraries this crate is based on. A crate that masks to a different width will produce
+//! different ids for the same config, and ids encoded by it have to be re-encoded.
+//!
+//! `Optimus::from_php_export` and `Optimus::from_go_export` read a config in the form those
+//! libraries print or construct it, so existing keys can be copied over as they are.
+
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::optimus::Optimus;
+
+impl Optimus {
+    ///Returns an Optimus struct from a config as written for the PHP library, either the
+    ///output of its `optimus spark` command or its constructor call:
+    ///
+    ///```text
+    ///Prime: 1580030173
+    ///Inverse: 59260789
+    ///Random: 1163945558
+    ///
+    ///new Optimus(1580030173, 59260789, 1163945558);
+    ///```
+    ///The values are validated with `new` and `validate`. Primes below `MIN_STRICT_PRIME`
+    ///are accepted, so existing keys with small primes can be migrated as they are.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Parse` if a value is missing or is not a non-negative
+    /// integer, or any error returned by `new` or `validate`
+    pub fn from_php_export(s: &str) -> Result<Self, OptimusError> {
+        from_export(s, ["prime", "inverse", "random"])
+    }
+    ///Returns an Optimus struct from a config as written for the Go library, either its
+    ///constructor call or the struct printed with `%+v`:
+    ///
+    ///```text
+    ///optimus.New(1580030173, 59260789, 1163945558)
+    ///
+    ///{prime:1580030173 modInverse:59260789 random:1163945558}
+    ///```
+    ///The values are validated with `new` and `validate`. Primes below `MIN_STRICT_PRIME`
+    ///are accepted, so existing keys with small primes can be migrated as they are.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Parse` if a value is missing or is not a non-negative
+    /// integer, or any error returned by `new` or `validate`
+    pub fn from_go_export(s: &str) -> Result<Self, OptimusError> {
+        from_export(s, ["prime", "modInverse", "random"])
+    }
+}
+
+fn from_export(s: &str, names: [&str; 3]) -> Result<Optimus, OptimusError> {
+    let [prime, mod_inverse, random] = match (s.find('('), s.rfind(')')) {
+        (Some(open), Some(close)) if open < close => arguments(&s[open + 1..close], names)?,
+        _ => [
+            field(s, names[0])?,
+            field(s, names[1])?,
+            field(s, names[2])?,
+        ],
+    };
+    let optimus = Optimus::new(prime, mod_inverse, random)?;
+    optimus.validate()?;
+    Ok(optimus)
+}
+
+///Parses the three comma separated arguments of a constructor call. A missing argument is
+///reported with its name in names, the same as for the `name:` form.
+fn arguments(args: &str, names: [&str; 3]) -> Result<[u64; 3], OptimusError> {
+    let args: Vec<&str> = args.split(',').map(str::trim).collect();
+    let number = |arg: &str| {
+        arg.parse()
+            .map_err(|_| OptimusError::Parse(ParseErrorKind::NotANumber(arg.to_string())))
+    };
+    match args[..] {
+        [prime, mod_inverse, random] => Ok([number(prime)?, number(mod_inverse)?, number(random)?]),
+        [_, _, _, ..] => Err(OptimusError::Parse(ParseErrorKind::TooManyFields {
+            expected: 3,
+        })),
+        _ => Err(OptimusError::Parse(ParseErrorKind::MissingField(
+            names[args.len()].to_string(),
+        ))),
+    }
+}
+
+///Reads the number after `name:`, matching name case-insensitively as a whole word.
+fn field(s: &str, name: &str) -> Result<u64, OptimusError> {
+    let lower = s.to_ascii_lowercase();
+    let key = format!("{}:", name.to_ascii_lowercase());
+    let start = lower
+        .match_indices(&key)
+        .map(|(i, _)| i)
+        .find(|&i| i == 0 || !lower.as_bytes()[i - 1].is_ascii_alphanumeric())
+        .ok_or_else(|| OptimusError::Parse(ParseErrorKind::MissingField(name.to_string())))?;
+    let value = s[start + key.len()..].trim_start();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value[..end]
+        .parse()
+        .map_err(|_| OptimusError::Parse(ParseErrorKind::NotANumber(name.to_string())))
+}
+
+///Aliases `encode`/`decode` as `encode_id`/`decode_id`.
+pub trait OptimusIdCompat {
+    ///Same as `Optimus::encode`.
+    fn encode_id(&self, id: u64) -> u64;
+    ///Same as `Optimus::decode`.
+    fn decode_id(&self, id: u64) -> u64;
+}
+
+impl OptimusIdCompat for Optimus {
+    fn encode_id(&self, id: u64) -> u64 {
+        self.encode(id)
+    }
+
+    fn decode_id(&self, id: u64) -> u64 {
+        self.decode(id)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    /// The config and value from the README, which follows the Go library's example.
+    #[test]
+    fn test_matches_readme() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.encode_id(15), 1103647397);
+        assert_eq!(o.decode_id(1103647397), 15);
+    }
+
+    #[test]
+    fn test_from_php_export() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let spark = "Prime: 1580030173\nInverse: 59260789\nRandom: 1163945558\n";
+        assert_eq!(Optimus::from_php_export(spark).unwrap(), o);
+        let constructor = "$optimus = new Optimus(1580030173, 59260789, 1163945558);";
+        assert_eq!(Optimus::from_php_export(constructor).unwrap(), o);
+        assert!(matches!(
+            Optimus::from_php_export("Prime: 1580030173\nRandom: 1163945558"),
+            Err(OptimusError::Parse(ParseErrorKind::MissingField(f))) if f == "inverse"
+        ));
+        assert!(matches!(
+            Optimus::from_php_export("Prime: 1580030173\nInverse: x\nRandom: 1163945558"),
+            Err(OptimusError::Parse(ParseErrorKind::NotANumber(f))) if f == "inverse"
+        ));
+    }
+
+    #[test]
+    fn test_from_go_export() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let constructor = "o := optimus.New(1580030173, 59260789, 1163945558)";
+        assert_eq!(Optimus::from_go_export(constructor).unwrap(), o);
+        let printed = "{prime:1580030173 modInverse:59260789 random:1163945558}";
+        assert_eq!(Optimus::from_go_export(printed).unwrap(), o);
+        assert!(matches!(
+            Optimus::from_go_export("optimus.New(1580030173, 59260789)"),
+            Err(OptimusError::Parse(ParseErrorKind::MissingField(f))) if f == "random"
+        ));
+        assert!(matches!(
+            Optimus::from_go_export("optimus.New(1, 2, 3, 4)"),
+            Err(OptimusError::Parse(ParseErrorKind::TooManyFields {
+                expected: 3
+            }))
+        ));
+        assert!(matches!(
+            Optimus::from_go_export("optimus.New(1580030173, 59260788, 1163945558)"),
+            Err(OptimusError::InvalidModInverse { .. })
+        ));
+    }
+
+    /// Both forms of each format name a missing field the way that format spells it.
+    #[test]
+    fn test_missing_field_names() {
+        let missing = |r: Result<Optimus, OptimusError>| match r {
+            Err(OptimusError::Parse(ParseErrorKind::MissingField(f))) => f,
+            other => panic!("expected MissingField, got {other:?}"),
+        };
+        let php_positional = Optimus::from_php_export("new Optimus(1580030173)");
+        assert_eq!(missing(php_positional), "inverse");
+        let php_named = Optimus::from_php_export("Prime: 1580030173\nRandom: 1163945558");
+        assert_eq!(missing(php_named), "inverse");
+        let go_positional = Optimus::from_go_export("optimus.New(1580030173)");
+        assert_eq!(missing(go_positional), "modInverse");
+        let go_named = Optimus::from_go_export("{prime:1580030173 random:1163945558}");
+        assert_eq!(missing(go_named), "modInverse");
+    }
+
+    /// Keys generated before `new_strict` existed may use primes below `MIN_STRICT_PRIME`.
+    #[test]
+    fn test_export_small_prime() {
+        let o = Optimus::new(1009, 521440529, 1163945558).unwrap();
+        let php = "Prime: 1009\nInverse: 521440529\nRandom: 1163945558\n";
+        assert_eq!(Optimus::from_php_export(php).unwrap(), o);
+        let go = "optimus.New(1009, 521440529, 1163945558)";
+        assert_eq!(Optimus::from_go_export(go).unwrap(), o);
+        assert!(matches!(
+            Optimus::from_go_export("optimus.New(1009, 521440529, 2147483648)"),
+            Err(OptimusError::RandomOutOfRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::optimus::Optimus;
+
+impl Optimus {
+    ///Returns an Optimus struct read from a TOML file containing the integer keys
+    ///`prime`, `mod_inverse` and `random`. The values are validated the same way as `new`.
+    ///
+    ///```toml
+    ///prime = 1580030173
+    ///mod_inverse = 59260789
+    ///random = 1163945558
+    ///```
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Io` if the file cannot be read, or `OptimusError::Parse`
+    /// with `ParseErrorKind::InvalidSyntax` if it is not TOML, `ParseErrorKind::MissingField`
+    /// if a key is missing or `ParseErrorKind::NotANumber` naming a key that is not a
+    /// non-negative integer, or any error returned by `new`
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, OptimusError> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&contents)
+    }
+
+    fn from_toml_str(contents: &str) -> Result<Self, OptimusError> {
+        let table = contents
+            .parse::<toml::Table>()
+            .map_err(|e| OptimusError::Parse(ParseErrorKind::InvalidSyntax(e.to_string())))?;
+        let key = |name: &str| -> Result<u64, OptimusError> {
+            let value = table.get(name).ok_or_else(|| {
+                OptimusError::Parse(ParseErrorKind::MissingField(name.to_string()))
+            })?;
+            value
+                .as_integer()
+                .and_then(|v| u64::try_from(v).ok())
+                .ok_or_else(|| OptimusError::Parse(ParseErrorKind::NotANumber(name.to_string())))
+        };
+        Self::new(key("prime")?, key("mod_inverse")?, key("random")?)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/optimus.toml");
+        let o = Optimus::from_toml_file(path).unwrap();
+        let id = 15;
+        assert_eq!(o.encode(id), 1103647397);
+        assert_eq!(o.decode(o.encode(id)), id);
+    }
+
+    #[test]
+    fn test_from_toml_str_errors() {
+        let missing = Optimus::from_toml_str("prime = 1580030173\nrandom = 1163945558");
+        match missing {
+            Err(OptimusError::Parse(ParseErrorKind::MissingField(key))) => {
+                assert_eq!(key, "mod_inverse");
+            }
+            other => panic!("expected MissingField, got {other:?}"),
+        }
+        let malformed =
+            Optimus::from_toml_str("prime = 1580030173\nmod_inverse = 59260789\nrandom = \"x\"");
+        match malformed {
+            Err(OptimusError::Parse(ParseErrorKind::NotANumber(key))) => assert_eq!(key, "random"),
+            other => panic!("expected NotANumber, got {other:?}"),
+        }
+        assert!(matches!(
+            Optimus::from_toml_str("prime = "),
+            Err(OptimusError::Parse(ParseErrorKind::InvalidSyntax(_)))
+        ));
+    }
+}
@@ -0,0 +1,51 @@
+use std::ops::Range;
+
+use subtle::{ConstantTimeGreater, ConstantTimeLess, CtOption};
+
+use crate::optimus::{Optimus, MAX_INT};
+
+impl Optimus {
+    ///Decodes e and returns the id only if e is a valid encoded value and the decoded id
+    ///falls within `allowed`, without branching on the secret or decoded values.
+    ///
+    ///This is intended for obfuscated ids that double as capability tokens. A plain
+    ///`decode` followed by an `if` on the result takes measurably different paths for
+    ///accepted and rejected tokens, which lets an attacker submitting many forged tokens
+    ///use response timing as an oracle about the decoded value. Here the range check is
+    ///done with constant time comparisons and the result is a `CtOption`, so timing does
+    ///not depend on whether the token was accepted.
+    ///
+    ///NOTE: this only hides the accepted/rejected decision. It does not make the
+    ///obfuscation itself cryptographically strong.
+    #[must_use]
+    pub fn decode_in_range(&self, e: u64, allowed: Range<u64>) -> CtOption<u64> {
+        // Masking keeps oversized e from overflowing decode; in_space still rejects it.
+        let id = self.decode(e & MAX_INT);
+        let in_space = !e.ct_gt(&MAX_INT);
+        let above_start = !allowed.start.ct_gt(&id);
+        let below_end = id.ct_lt(&allowed.end);
+        CtOption::new(id, in_space & above_start & below_end)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_in_range() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let e = o.encode(15);
+        assert_eq!(o.decode_in_range(e, 0..16).unwrap(), 15);
+        assert_eq!(o.decode_in_range(e, 15..16).unwrap(), 15);
+        assert!(bool::from(o.decode_in_range(e, 0..15).is_none()));
+        assert!(bool::from(o.decode_in_range(e, 16..100).is_none()));
+        assert!(bool::from(
+            o.decode_in_range(MAX_INT + 1, 0..u64::MAX).is_none()
+        ));
+        assert!(bool::from(
+            o.decode_in_range(u64::MAX, 0..u64::MAX).is_none()
+        ));
+    }
+}
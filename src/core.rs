@@ -0,0 +1,68 @@
+//! The encode and decode arithmetic with no dependencies and no validation. This module is
+//! always available, including under `#![no_std]` with default features disabled.
+
+///The largest id that can be encoded and the largest value encode produces.
+pub const MAX_INT: u64 = i32::MAX as u64;
+
+///`RawOptimus` holds a config and encodes and decodes with it, without checking that the
+///config is valid. Use `Optimus` to validate a config, e.g. that prime is really prime and
+///that modInverse is its inverse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawOptimus {
+    prime: u64,
+    mod_inverse: u64,
+    random: u64,
+}
+
+impl RawOptimus {
+    ///Returns a `RawOptimus` for the given config without validating it. An invalid config
+    ///encodes without error but will not decode back to the original values.
+    #[must_use]
+    pub const fn new_unchecked(prime: u64, mod_inverse: u64, random: u64) -> Self {
+        Self {
+            prime,
+            mod_inverse,
+            random,
+        }
+    }
+    ///Encodes n using Knuth's hashing algorithm.
+    ///
+    ///In debug builds this panics if `n * prime` overflows a u64, which only happens for an
+    ///out of range n or an oversized prime. Release builds wrap without a branch.
+    #[must_use]
+    pub const fn encode(&self, n: u64) -> u64 {
+        debug_assert!(
+            n.checked_mul(self.prime).is_some(),
+            "encode overflow: n * prime does not fit in a u64"
+        );
+        (n.wrapping_mul(self.prime) & MAX_INT) ^ self.random
+    }
+    ///Decodes n back to the original. It will only decode correctly if the config is
+    ///consistent with what was used to encode n.
+    ///
+    ///In debug builds this panics if `(n ^ random) * mod_inverse` overflows a u64.
+    ///Release builds wrap without a branch.
+    #[must_use]
+    pub const fn decode(&self, n: u64) -> u64 {
+        debug_assert!(
+            (n ^ self.random).checked_mul(self.mod_inverse).is_some(),
+            "decode overflow: (n ^ random) * mod_inverse does not fit in a u64"
+        );
+        ((n ^ self.random).wrapping_mul(self.mod_inverse)) & MAX_INT
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_optimus() {
+        const RAW: RawOptimus = RawOptimus::new_unchecked(1580030173, 59260789, 1163945558);
+        assert_eq!(RAW.encode(15), 1103647397);
+        for n in [0, 1, 15, MAX_INT - 1, MAX_INT] {
+            assert_eq!(RAW.decode(RAW.encode(n)), n);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::base62::{to_radix, BASE62_ALPHABET};
+use crate::error::OptimusError;
+use crate::optimus::{check_range, Optimus};
+
+///Encoded is an id produced by `Optimus::encode_typed`. Keeping encoded ids in their own
+///type stops them from being mixed up with, or encoded again as, internal ids.
+///
+///Encoded implements `Ord` and `Hash` so it can be used as a `BTreeMap` or `HashMap` key
+///and sorted for stable storage. The ordering is over the encoded representation and
+///says nothing about the order of the original ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Encoded(pub u64);
+
+impl From<Encoded> for u64 {
+    fn from(e: Encoded) -> Self {
+        e.0
+    }
+}
+
+impl fmt::Display for Encoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+///`IdToken` is returned by `Optimus::token_for`. It holds one encoded id and renders it in
+///each output format without encoding it again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdToken {
+    encoded: u64,
+}
+
+impl IdToken {
+    ///Returns the encoded id, as `Optimus::encode` does.
+    #[must_use]
+    pub fn as_u64(&self) -> u64 {
+        self.encoded
+    }
+    ///Returns the encoded id as base62, as `Optimus::encode_base62` does.
+    #[must_use]
+    pub fn as_base62(&self) -> String {
+        to_radix(BASE62_ALPHABET, self.encoded)
+    }
+    ///Returns the encoded id as 8 lowercase hex characters.
+    #[must_use]
+    pub fn as_hex(&self) -> String {
+        format!("{:08x}", self.encoded)
+    }
+    ///Returns the encoded id as 4 big-endian bytes, as `Optimus::encode_bytes` does.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn as_bytes(&self) -> [u8; 4] {
+        (self.encoded as u32).to_be_bytes()
+    }
+}
+
+impl Optimus {
+    ///Encodes n once and returns an `IdToken` that renders it in every output format, for
+    ///code that needs the same id as a number, base62, hex and bytes.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if n is greater than `MAX_INT`
+    pub fn token_for(&self, n: u64) -> Result<IdToken, OptimusError> {
+        check_range(n)?;
+        Ok(IdToken {
+            encoded: self.encode(n),
+        })
+    }
+    ///Encodes n like `encode`, returning the result as an `Encoded`.
+    #[must_use]
+    pub fn encode_typed(&self, n: u64) -> Encoded {
+        Encoded(self.encode(n))
+    }
+    ///Decodes an `Encoded` back to the original id.
+    #[must_use]
+    pub fn decode_typed(&self, e: Encoded) -> u64 {
+        self.decode(e.0)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::optimus::MAX_INT;
+
+    #[test]
+    fn test_encoded_ordering() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let set: BTreeSet<Encoded> = (10..20).map(|n| o.encode_typed(n)).collect();
+        assert_eq!(set.len(), 10);
+        let sorted: Vec<u64> = set.iter().map(|&e| u64::from(e)).collect();
+        assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+        assert!(set.contains(&Encoded(1103647397)));
+        assert_eq!(o.decode_typed(Encoded(1103647397)), 15);
+        assert_eq!(Encoded(1103647397).to_string(), "1103647397");
+    }
+
+    #[test]
+    fn test_token_for() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        for id in [0, 15, MAX_INT] {
+            let token = o.token_for(id).unwrap();
+            assert_eq!(token.as_u64(), o.encode(id));
+            assert_eq!(token.as_base62(), o.encode_base62(id));
+            assert_eq!(token.as_bytes(), o.encode_bytes(id));
+            assert_eq!(
+                u64::from_str_radix(&token.as_hex(), 16).unwrap(),
+                o.encode(id)
+            );
+        }
+        assert_eq!(o.token_for(15).unwrap().as_hex(), "41c852a5");
+        assert!(matches!(
+            o.token_for(MAX_INT + 1),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+    }
+}
@@ -6,4 +6,115 @@ pub enum OptimusError {
     NotPrime,
     #[error("Cannoot calculate Mod Inverse for Argument Provided")]
     NoModInverse,
+    #[error("no mod inverse found for odd prime {prime}, which always has one; this is a bug")]
+    UnexpectedNoInverse { prime: u64 },
+    #[error("prime {prime} shares a factor with modulus {modulus} and has no inverse")]
+    NotCoprime { prime: u64, modulus: u64 },
+    #[error("input {value} exceeds maximum {max}")]
+    InputOutOfRange { value: u64, max: u64 },
+    #[error("decoded id {value} is above the expected maximum {real_max}")]
+    DecodedOutOfExpectedRange { value: u64, real_max: u64 },
+    #[error("prime {value} exceeds maximum {max}")]
+    PrimeOutOfRange { value: u64, max: u64 },
+    #[error("random {value} exceeds maximum {max}")]
+    RandomOutOfRange { value: u64, max: u64 },
+    #[error("mod inverse {mod_inverse} is not the inverse of prime {prime}")]
+    InvalidModInverse { prime: u64, mod_inverse: u64 },
+    #[error("prime {value} is below the minimum {min}")]
+    DegeneratePrime { value: u64, min: u64 },
+    #[error("width of {bits} bits is not within 1 to {max}")]
+    InvalidWidth { bits: u32, max: u32 },
+    #[error("cannot preserve {bits} high bits, at most {max} leave enough bits to obfuscate")]
+    TooManyPreservedBits { bits: u32, max: u32 },
+    #[error(
+        "cannot reserve ids below {reserved_below}, at most {max} leave enough ids to obfuscate"
+    )]
+    ReservedTooLarge { reserved_below: u64, max: u64 },
+    #[error("shares its prime with `{other}`")]
+    SharedPrime { other: String },
+    #[error("Value Is Not A Valid Encoding")]
+    InvalidEncoding,
+    #[error("every encoding of the id contains a blocked word")]
+    NoCleanEncoding,
+    #[error("not enough data to recover a config")]
+    InsufficientData,
+    #[error("{value} is already encoded")]
+    AlreadyEncoded { value: u64 },
+    #[error("unknown token version {0:?}")]
+    UnknownVersion(char),
+    #[error("{rounds} rounds is fewer than the minimum {min}")]
+    TooFewRounds { rounds: u8, min: u8 },
+    #[error("Invalid KDF Input: {0}")]
+    InvalidKdfInput(&'static str),
+    #[error("input does not fit in a u64")]
+    ConversionOverflow,
+    #[error("input {value} is negative")]
+    NegativeInput { value: i64 },
+    #[error("Invalid Input: {0}")]
+    Parse(ParseErrorKind),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+///Lets `io::Result` returning code use `?` on crate errors. `OptimusError::Io` gives back
+///the wrapped error, anything else becomes `ErrorKind::InvalidData` with the same message.
+impl From<OptimusError> for std::io::Error {
+    fn from(e: OptimusError) -> Self {
+        match e {
+            OptimusError::Io(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+///The reason a string or byte input could not be parsed, carried by `OptimusError::Parse`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    #[error("empty input")]
+    EmptyInput,
+    #[error("`{0}` is not a non-negative integer")]
+    NotANumber(String),
+    #[error("missing field `{0}`")]
+    MissingField(String),
+    #[error("too many fields, expected {expected}")]
+    TooManyFields { expected: usize },
+    #[error("character {0:?} is not in the alphabet")]
+    OutOfAlphabet(char),
+    #[error("expected {expected} characters or bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("{0}")]
+    InvalidSyntax(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_error_message() {
+        let e = OptimusError::InputOutOfRange {
+            value: 2_147_483_648,
+            max: 2_147_483_647,
+        };
+        assert_eq!(e.to_string(), "input 2147483648 exceeds maximum 2147483647");
+    }
+
+    #[test]
+    fn test_parse_error_message() {
+        let e = OptimusError::Parse(ParseErrorKind::MissingField("prime".to_string()));
+        assert_eq!(e.to_string(), "Invalid Input: missing field `prime`");
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        let e = std::io::Error::from(OptimusError::InputOutOfRange {
+            value: 2_147_483_648,
+            max: 2_147_483_647,
+        });
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "input 2147483648 exceeds maximum 2147483647");
+        let inner = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof");
+        let e = std::io::Error::from(OptimusError::Io(inner));
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
 }
@@ -0,0 +1,116 @@
+//! A keyed Feistel network over the id space, as a stronger alternative to the linear
+//! `Optimus` scheme behind the same `Transform` interface.
+
+use crate::error::OptimusError;
+use crate::mix::siphash24;
+use crate::optimus::MAX_INT;
+use crate::transform::Transform;
+
+///The fewest rounds `Feistel::new` accepts. Fewer than 4 rounds of a Feistel network are
+///distinguishable from a random permutation.
+pub const MIN_FEISTEL_ROUNDS: u8 = 4;
+
+///`Feistel` permutes `0..=MAX_INT` with a balanced Feistel network on 32 bits, using
+///SipHash-2-4 keyed with a 128 bit key as the round function. Outputs above `MAX_INT` are
+///fed through the network again (cycle-walking) until they land back in the id space.
+///
+///Unlike `Optimus`, a few known (id, encoded) pairs do not reveal the key, as recovering
+///it means breaking the round function. That strength costs speed: every encode hashes once
+///per round and makes on average two passes through the network, where `Optimus` does one
+///multiply and xor. Run `cargo bench` to compare them on your hardware.
+///
+///NOTE: this is a well known construction built from a standard PRF, but it has not been
+///independently reviewed as a cipher. Ids are still not authenticated: any value decodes
+///to some id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Feistel {
+    key: [u8; 16],
+    rounds: u8,
+}
+
+impl Feistel {
+    ///Returns a `Feistel` transform for key, running rounds rounds per pass.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::TooFewRounds` if rounds is less than `MIN_FEISTEL_ROUNDS`
+    pub fn new(key: [u8; 16], rounds: u8) -> Result<Self, OptimusError> {
+        if rounds < MIN_FEISTEL_ROUNDS {
+            return Err(OptimusError::TooFewRounds {
+                rounds,
+                min: MIN_FEISTEL_ROUNDS,
+            });
+        }
+        Ok(Self { key, rounds })
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn round(&self, round: u8, half: u16) -> u16 {
+        siphash24(&self.key, (u64::from(round) << 16) | u64::from(half)) as u16
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn permute(&self, n: u32) -> u32 {
+        let (mut l, mut r) = ((n >> 16) as u16, n as u16);
+        for i in 0..self.rounds {
+            (l, r) = (r, l ^ self.round(i, r));
+        }
+        (u32::from(l) << 16) | u32::from(r)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn unpermute(&self, n: u32) -> u32 {
+        let (mut l, mut r) = ((n >> 16) as u16, n as u16);
+        for i in (0..self.rounds).rev() {
+            (l, r) = (r ^ self.round(i, l), l);
+        }
+        (u32::from(l) << 16) | u32::from(r)
+    }
+
+    ///Runs f until the value is back in the id space. Starting inside the id space this
+    ///always ends, at the latest when the walk comes back around the permutation's cycle.
+    #[allow(clippy::cast_possible_truncation)]
+    fn walk(n: u64, f: impl Fn(u32) -> u32) -> u64 {
+        let mut v = f((n & MAX_INT) as u32);
+        while u64::from(v) > MAX_INT {
+            v = f(v);
+        }
+        u64::from(v)
+    }
+}
+
+impl Transform for Feistel {
+    ///Encodes n, reduced modulo `MAX_INT + 1` like `Optimus::encode_wrapping` so the walk
+    ///always starts inside the id space.
+    fn encode(&self, n: u64) -> u64 {
+        Self::walk(n, |v| self.permute(v))
+    }
+
+    fn decode(&self, n: u64) -> u64 {
+        Self::walk(n, |v| self.unpermute(v))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feistel() {
+        let f = Feistel::new(*b"optimus-rs tests", 8).unwrap();
+        for n in (0..16).chain(MAX_INT - 16..=MAX_INT) {
+            let e = f.encode(n);
+            assert!(e <= MAX_INT);
+            assert_eq!(f.decode(e), n);
+        }
+        let mut out = String::new();
+        f.encode_base62_into(&[0, 15, MAX_INT], &mut out);
+        assert_eq!(f.decode_base62_slice(&out).unwrap(), [0, 15, MAX_INT]);
+        let other = Feistel::new(*b"optimus-rs other", 8).unwrap();
+        assert_ne!(f.encode(15), other.encode(15));
+        assert!(matches!(
+            Feistel::new([0; 16], 3),
+            Err(OptimusError::TooFewRounds { rounds: 3, min: 4 })
+        ));
+    }
+}
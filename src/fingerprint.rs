@@ -0,0 +1,49 @@
+use sha2::{Digest, Sha256};
+
+use crate::optimus::Optimus;
+
+impl Optimus {
+    ///Returns the first 8 bytes of the SHA-256 of prime, modInverse and random, for
+    ///checking that the nodes of a fleet use the same config without exchanging it. Equal
+    ///configs always have equal fingerprints, in this and future versions of the crate.
+    ///
+    ///NOTE: the fingerprint is one-way, so it can be logged where the config can not, but it
+    ///is not a substitute for keeping the config secret. There are only about 2^56 possible
+    ///configs, few enough that a determined attacker could search them for a fingerprint.
+    #[must_use]
+    pub fn fingerprint(&self) -> [u8; 8] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"optimus-fingerprint-v1");
+        for field in [self.prime, self.mod_inverse, self.random] {
+            hasher.update(field.to_be_bytes());
+        }
+        let digest = hasher.finalize();
+        let mut fingerprint = [0; 8];
+        fingerprint.copy_from_slice(&digest[..8]);
+        fingerprint
+    }
+    ///Returns true if fp is this config's `fingerprint`.
+    #[must_use]
+    pub fn matches_fingerprint(&self, fp: [u8; 8]) -> bool {
+        self.fingerprint() == fp
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let a = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let b = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert!(a.matches_fingerprint(b.fingerprint()));
+        let c = a.with_random(1163945559).unwrap();
+        assert_ne!(a.fingerprint(), c.fingerprint());
+        assert!(!a.matches_fingerprint(c.fingerprint()));
+        // Fingerprints must never change between releases.
+        assert_eq!(a.fingerprint(), [111, 12, 143, 195, 136, 245, 141, 197]);
+    }
+}
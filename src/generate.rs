@@ -0,0 +1,198 @@
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+#[cfg(feature = "rand")]
+use rand::Rng;
+use sha2::Sha256;
+
+#[cfg(feature = "rand")]
+use crate::error::OptimusError;
+use crate::mix::splitmix64;
+use crate::optimus::{inverse_pow2, Optimus, MAX_INT};
+
+///The smallest prime `from_seed` will pick, so generated multipliers are always large.
+pub const MIN_GENERATED_PRIME: u64 = 1 << 30;
+
+impl Optimus {
+    ///Returns an Optimus struct derived deterministically from seed. The same seed always
+    ///produces the same config, in this and future versions of the crate, and the prime is
+    ///always between `MIN_GENERATED_PRIME` and `MAX_INT`.
+    ///
+    ///CAUTION: anyone who knows the seed can recreate the config, so treat it as a secret.
+    #[must_use]
+    pub fn from_seed(seed: u64) -> Self {
+        let mut state = seed;
+        let span = MAX_INT - MIN_GENERATED_PRIME + 1;
+        let prime = loop {
+            let candidate = (MIN_GENERATED_PRIME + splitmix64(&mut state) % span) | 1;
+            if primal_check::miller_rabin(candidate) {
+                break candidate;
+            }
+        };
+        Self {
+            prime,
+            mod_inverse: inverse_pow2(prime) & MAX_INT,
+            random: splitmix64(&mut state) & MAX_INT,
+        }
+    }
+    ///Returns a config derived deterministically from this one and label, so one stored
+    ///config can fan out into independent configs per use, e.g. `"session"` and `"invite"`.
+    ///The same config and label always give the same result, in this and future versions
+    ///of the crate, and different labels give unrelated primes and randoms.
+    ///
+    ///The derived config is `from_seed` of the first 8 bytes of an HMAC-SHA256 of label,
+    ///keyed with prime, modInverse and random. A leaked derived config does not reveal this
+    ///config or the configs derived for other labels, but this config reveals all of them,
+    ///so it needs the same protection as any other config.
+    #[must_use]
+    pub fn derive_labeled(&self, label: &str) -> Self {
+        // Keys shorter than the 64 byte block are zero padded by HMAC, so padding here
+        // gives the same MAC without a fallible length check.
+        let mut key = [0u8; 64];
+        key[..8].copy_from_slice(&self.prime.to_be_bytes());
+        key[8..16].copy_from_slice(&self.mod_inverse.to_be_bytes());
+        key[16..24].copy_from_slice(&self.random.to_be_bytes());
+        let mut mac = <Hmac<Sha256> as KeyInit>::new(&key.into());
+        mac.update(label.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&digest[..8]);
+        Self::from_seed(u64::from_be_bytes(seed))
+    }
+    ///Returns a new random config drawn from rng, with a prime between
+    ///`MIN_GENERATED_PRIME` and `MAX_INT`. Use the getters to record it.
+    #[cfg(feature = "rand")]
+    pub fn generate<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::from_seed(rng.gen())
+    }
+    ///Returns a new random config like `generate`, whose prime is not one of `exclude`, e.g.
+    ///the primes already assigned to other tenants. Prime uniqueness across a fleet then only
+    ///requires the caller to keep track of the assigned primes.
+    ///
+    ///NOTE: there are tens of millions of primes in the generated range, so this only loops
+    ///for long if exclude lists a large share of them.
+    #[cfg(feature = "rand")]
+    pub fn random_excluding<R: Rng + ?Sized>(rng: &mut R, exclude: &[u64]) -> Self {
+        loop {
+            let optimus = Self::generate(rng);
+            if !exclude.contains(&optimus.prime) {
+                return optimus;
+            }
+        }
+    }
+    ///Returns an Optimus struct for a prime you picked yourself, calculating its mod inverse
+    ///and drawing a fresh random from rng. Use the `mod_inverse` and `random` getters to
+    ///record the rest of the config.
+    /// # Errors
+    ///
+    /// Will return `OptimusError` if the argument `prime` is not prime, is greater than
+    /// `MAX_INT`, or if a Mod Inverse cannot be found
+    #[cfg(feature = "rand")]
+    pub fn from_prime<R: Rng + ?Sized>(prime: u64, rng: &mut R) -> Result<Optimus, OptimusError> {
+        Self::new_calculated(prime, rng.gen_range(0..=MAX_INT))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_seed() {
+        for seed in [0, 1, 42, u64::MAX] {
+            let o = Optimus::from_seed(seed);
+            assert_eq!(o, Optimus::from_seed(seed));
+            assert!(o.validate().is_ok(), "{o:?}");
+            assert!(o.prime() >= MIN_GENERATED_PRIME);
+            assert_eq!(o.decode(o.encode(15)), 15);
+        }
+        assert_ne!(Optimus::from_seed(1), Optimus::from_seed(2));
+        // Generated configs must never change between releases.
+        assert_eq!(
+            Optimus::from_seed(0),
+            Optimus::new(1917616621, 1928463333, 1369994395).unwrap()
+        );
+    }
+
+    /// Every generated config must be a bijection over `0..=MAX_INT`. Checking a random
+    /// sample plus the edges of the range for collisions keeps this fast.
+    #[test]
+    fn test_from_seed_is_permutation() {
+        let mut state = 0x5eed;
+        for _ in 0..64 {
+            let o = Optimus::from_seed(splitmix64(&mut state));
+            let mut inputs: Vec<u64> = (0..2048)
+                .map(|_| splitmix64(&mut state) & MAX_INT)
+                .collect();
+            inputs.extend([0, 1, 2, MAX_INT - 1, MAX_INT]);
+            inputs.sort_unstable();
+            inputs.dedup();
+            let mut outputs = std::collections::HashSet::with_capacity(inputs.len());
+            for &n in &inputs {
+                let e = o.encode(n);
+                assert!(e <= MAX_INT, "{o:?} encodes {n} out of range");
+                assert!(outputs.insert(e), "{o:?} maps two inputs to {e}");
+                assert_eq!(o.decode(e), n, "{o:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_derive_labeled() {
+        let base = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let session = base.derive_labeled("session");
+        let invite = base.derive_labeled("invite");
+        assert_eq!(session, base.derive_labeled("session"));
+        assert!(session.validate().is_ok());
+        assert_ne!(session.prime(), invite.prime());
+        assert_ne!(session.encode(15), invite.encode(15));
+        assert_ne!(session.encode(15), base.encode(15));
+        let other = base.with_random(42).unwrap().derive_labeled("session");
+        assert_ne!(other, session);
+        // Derived configs must never change between releases.
+        assert_eq!(
+            session,
+            Optimus::new(2126297839, 1284486159, 1908283603).unwrap()
+        );
+        // The seed is the first 8 bytes of HMAC-SHA256 of "session", keyed with the config.
+        assert_eq!(session, Optimus::from_seed(8721430186841421205));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random_excluding() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let o = Optimus::generate(&mut StdRng::seed_from_u64(7));
+        assert!(o.validate().is_ok());
+        assert!(o.prime() >= MIN_GENERATED_PRIME);
+        // The first prime drawn from this rng is excluded, so a later one has to be used.
+        let excluded = [o.prime(), 1580030173];
+        let other = Optimus::random_excluding(&mut StdRng::seed_from_u64(7), &excluded);
+        assert!(!excluded.contains(&other.prime()));
+        assert!(other.validate().is_ok());
+        let mut rng = rand::thread_rng();
+        let assigned: Vec<u64> = (0..8)
+            .map(|_| Optimus::generate(&mut rng).prime())
+            .collect();
+        for _ in 0..32 {
+            let o = Optimus::random_excluding(&mut rng, &assigned);
+            assert!(!assigned.contains(&o.prime()));
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_from_prime() {
+        let mut rng = rand::thread_rng();
+        let o = Optimus::from_prime(1580030173, &mut rng).unwrap();
+        assert_eq!(o.prime(), 1580030173);
+        assert_eq!(o.mod_inverse(), 59260789);
+        assert!(o.random() <= MAX_INT);
+        assert!(o.validate().is_ok());
+        assert_eq!(o.decode(o.encode(15)), 15);
+        assert!(matches!(
+            Optimus::from_prime(1580030174, &mut rng),
+            Err(OptimusError::NotPrime)
+        ));
+    }
+}
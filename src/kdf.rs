@@ -0,0 +1,62 @@
+use sha2::Sha256;
+
+use crate::error::OptimusError;
+use crate::optimus::Optimus;
+
+///The number of PBKDF2-HMAC-SHA256 iterations used by `from_passphrase`.
+pub const KDF_ROUNDS: u32 = 600_000;
+///The shortest salt accepted by `from_passphrase`.
+pub const MIN_SALT_LEN: usize = 8;
+
+impl Optimus {
+    ///Returns an Optimus struct derived from a passphrase, so a single secret phrase
+    ///can be managed instead of three numbers. The passphrase and salt are run through
+    ///PBKDF2-HMAC-SHA256 with `KDF_ROUNDS` (600 000) iterations to produce an 8 byte
+    ///seed, which is passed to `from_seed`. The same passphrase and salt always give
+    ///the same config.
+    ///
+    ///This is deliberately slow; derive the config once at startup rather than per request.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InvalidKdfInput` if passphrase is empty or salt is shorter
+    /// than `MIN_SALT_LEN` (8) bytes
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, OptimusError> {
+        if passphrase.is_empty() {
+            return Err(OptimusError::InvalidKdfInput(
+                "passphrase must not be empty",
+            ));
+        }
+        if salt.len() < MIN_SALT_LEN {
+            return Err(OptimusError::InvalidKdfInput("salt is too short"));
+        }
+        let mut seed = [0u8; 8];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut seed);
+        Ok(Self::from_seed(u64::from_be_bytes(seed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_passphrase() {
+        let salt = b"optimus-rs salt";
+        let a = Optimus::from_passphrase("correct horse battery staple", salt).unwrap();
+        assert_eq!(
+            a,
+            Optimus::from_passphrase("correct horse battery staple", salt).unwrap()
+        );
+        assert!(a.validate().is_ok());
+        let b = Optimus::from_passphrase("correct horse battery stapler", salt).unwrap();
+        assert_ne!(a, b);
+        assert!(matches!(
+            Optimus::from_passphrase("", salt),
+            Err(OptimusError::InvalidKdfInput(_))
+        ));
+        assert!(matches!(
+            Optimus::from_passphrase("passphrase", b"short"),
+            Err(OptimusError::InvalidKdfInput(_))
+        ));
+    }
+}
@@ -1,8 +1,67 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(clippy::pedantic)]
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "analysis")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod base62;
+#[cfg(feature = "std")]
+pub mod base64;
+#[cfg(feature = "std")]
+mod card;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "toml")]
+mod config;
+#[cfg(feature = "subtle")]
+mod constant_time;
+pub mod core;
+#[cfg(feature = "std")]
+pub mod encoded;
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "std")]
+pub mod feistel;
+#[cfg(feature = "std")]
+mod fingerprint;
+#[cfg(feature = "std")]
+mod generate;
+#[cfg(feature = "kdf")]
+mod kdf;
+#[cfg(feature = "std")]
+mod mix;
+#[cfg(feature = "std")]
+pub mod modulus;
+#[cfg(feature = "std")]
+mod nonce;
+#[cfg(feature = "std")]
 pub mod optimus;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "std")]
+mod telemetry;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod testing;
+#[cfg(feature = "std")]
+pub mod token;
+#[cfg(feature = "std")]
+pub mod transform;
 
-pub use crate::error::OptimusError;
-pub use crate::optimus::Optimus;
+pub use crate::core::RawOptimus;
+#[cfg(feature = "std")]
+pub use crate::encoded::{Encoded, IdToken};
+#[cfg(feature = "std")]
+pub use crate::error::{OptimusError, ParseErrorKind};
+#[cfg(feature = "std")]
+pub use crate::feistel::Feistel;
+#[cfg(feature = "std")]
+pub use crate::generate::MIN_GENERATED_PRIME;
+#[cfg(feature = "kdf")]
+pub use crate::kdf::{KDF_ROUNDS, MIN_SALT_LEN};
+#[cfg(feature = "std")]
+pub use crate::modulus::Modulus;
+#[cfg(feature = "std")]
+pub use crate::optimus::{DecodeKey, DecodeResult, EncodeKey, Inverse, Optimus, Resolution};
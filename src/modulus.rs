@@ -0,0 +1,66 @@
+use crate::error::OptimusError;
+use crate::optimus::MAX_INT;
+
+///A power of two modulus `2^bits` for the id space, constructed from its bit count so it
+///can not hold any other value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modulus {
+    bits: u32,
+}
+
+impl Modulus {
+    ///The modulus `encode` and `decode` use, 2^31, i.e. ids up to `MAX_INT`.
+    pub const MAX_INT: Modulus = Modulus {
+        bits: MAX_INT.count_ones(),
+    };
+    ///Returns the modulus `2^bits`.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InvalidWidth` if bits is not within `1..=64`
+    pub fn from_bits(bits: u32) -> Result<Self, OptimusError> {
+        if !(1..=u64::BITS).contains(&bits) {
+            return Err(OptimusError::InvalidWidth {
+                bits,
+                max: u64::BITS,
+            });
+        }
+        Ok(Self { bits })
+    }
+    ///Returns the number of bits of the id space.
+    #[must_use]
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+    ///Returns `2^bits - 1`, the largest value in the id space and the mask applied
+    ///after multiplying.
+    #[must_use]
+    pub fn mask(&self) -> u64 {
+        u64::MAX >> (u64::BITS - self.bits)
+    }
+}
+
+impl Default for Modulus {
+    fn default() -> Self {
+        Self::MAX_INT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modulus() {
+        assert_eq!(Modulus::default(), Modulus::MAX_INT);
+        assert_eq!(Modulus::MAX_INT.bits(), 31);
+        assert_eq!(Modulus::MAX_INT.mask(), MAX_INT);
+        assert_eq!(Modulus::from_bits(1).unwrap().mask(), 1);
+        assert_eq!(Modulus::from_bits(64).unwrap().mask(), u64::MAX);
+        for bits in [0, 65, u32::MAX] {
+            assert!(matches!(
+                Modulus::from_bits(bits),
+                Err(OptimusError::InvalidWidth { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::optimus::{Optimus, MAX_INT};
+
+const HALF_LEN: usize = 8;
+
+impl Optimus {
+    ///Encodes n into a token that is different on every call, so that links sent for the
+    ///same id can not be correlated with each other.
+    ///
+    ///The token is 16 lowercase hex characters. The first 8 are a random nonce no greater
+    ///than `MAX_INT` and the last 8 are `encode((n + nonce) & MAX_INT)`. Every token for n
+    ///decodes back to n with `decode_nondeterministic`.
+    #[cfg(feature = "rand")]
+    pub fn encode_nondeterministic<R: Rng + ?Sized>(&self, n: u64, rng: &mut R) -> String {
+        let nonce = rng.gen_range(0..=MAX_INT);
+        let body = self.encode(n.wrapping_add(nonce) & MAX_INT);
+        format!("{nonce:08x}{body:08x}")
+    }
+    ///Decodes a token produced by `encode_nondeterministic` back to the original id.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Parse` with `ParseErrorKind::InvalidLength` if the token is
+    /// not 16 characters or `ParseErrorKind::OutOfAlphabet` if it is not hex, or
+    /// `OptimusError::InputOutOfRange` if either half is above `MAX_INT`
+    pub fn decode_nondeterministic(&self, token: &str) -> Result<u64, OptimusError> {
+        if token.len() != 2 * HALF_LEN {
+            return Err(OptimusError::Parse(ParseErrorKind::InvalidLength {
+                expected: 2 * HALF_LEN,
+                actual: token.len(),
+            }));
+        }
+        let value = token.chars().try_fold(0u64, |acc, c| {
+            c.to_digit(16)
+                .map(|d| (acc << 4) | u64::from(d))
+                .ok_or(OptimusError::Parse(ParseErrorKind::OutOfAlphabet(c)))
+        })?;
+        let (nonce, body) = (value >> 32, value & 0xFFFF_FFFF);
+        if let Some(value) = [nonce, body].into_iter().find(|&v| v > MAX_INT) {
+            return Err(OptimusError::InputOutOfRange {
+                value,
+                max: MAX_INT,
+            });
+        }
+        Ok((self.decode(body) + (MAX_INT + 1) - nonce) & MAX_INT)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_encode_nondeterministic() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let mut rng = rand::thread_rng();
+        for id in [0, 15, MAX_INT] {
+            let a = o.encode_nondeterministic(id, &mut rng);
+            let b = o.encode_nondeterministic(id, &mut rng);
+            assert_ne!(a, b);
+            assert_eq!(o.decode_nondeterministic(&a).unwrap(), id);
+            assert_eq!(o.decode_nondeterministic(&b).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_decode_nondeterministic_errors() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.decode_nondeterministic("0000000041c852a5").unwrap(), 15);
+        assert!(matches!(
+            o.decode_nondeterministic("0000000041c852a"),
+            Err(OptimusError::Parse(ParseErrorKind::InvalidLength {
+                expected: 16,
+                actual: 15
+            }))
+        ));
+        assert!(matches!(
+            o.decode_nondeterministic("+000000041c852a5"),
+            Err(OptimusError::Parse(ParseErrorKind::OutOfAlphabet('+')))
+        ));
+        assert!(matches!(
+            o.decode_nondeterministic("8000000041c852a5"),
+            Err(OptimusError::InputOutOfRange {
+                value: 0x8000_0000,
+                max: MAX_INT
+            })
+        ));
+    }
+}
@@ -1,16 +1,93 @@
-use crate::error::OptimusError;
+use std::collections::HashMap;
+use std::ops::Range;
 
-pub const MAX_INT: u64 = i32::MAX as u64;
+use crate::core::RawOptimus;
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::mix::{fnv1a64, splitmix64};
+use crate::modulus::Modulus;
+use crate::telemetry;
+
+pub use crate::core::MAX_INT;
+
+const ID_BITS: u32 = MAX_INT.count_ones();
+///The fewest low bits `encode_low` will obfuscate.
+pub const MIN_OBFUSCATED_BITS: u32 = 8;
+
+///Number of high bits holding the timestamp in an `encode_timestamped` id.
+pub const TIMESTAMP_BITS: u32 = 24;
+///Number of low bits holding the sequence number in an `encode_timestamped` id.
+pub const SEQUENCE_BITS: u32 = ID_BITS - TIMESTAMP_BITS;
+
+///Version of the encode and decode math. It is bumped, deliberately and in review, by any
+///change that makes a config produce different output for the same input, so services
+///can refuse to start against a version their stored ids were not encoded with.
+pub const ALGORITHM_VERSION: u32 = 1;
+
+///Smallest prime accepted by `new_strict`. Below it, the product of prime and a small id
+///never wraps around 2^31, so the encoded values of nearby small ids are just multiples of
+///prime xored with random, which makes the pattern easy to spot.
+pub const MIN_STRICT_PRIME: u64 = 1 << 16;
+
+///Largest `reserved_below` accepted by `encode_partitioned`. It keeps the obfuscated part
+///at least half of the id space, so cycle-walking into it takes 2 steps on average.
+pub const MAX_RESERVED: u64 = 1 << 30;
+
+///Bit set on every `encode_once` output, the highest bit below `MAX_INT`.
+pub const ENCODED_MARKER: u64 = 1 << (ID_BITS - 1);
+
+///Number of bits available to each half of an `encode_pair` id.
+pub const PAIR_BITS: u32 = 15;
+const PAIR_MASK: u64 = (1 << PAIR_BITS) - 1;
+
+///Returns the inverse of an odd n modulo 2^64 using Newton's iteration. Masking the result
+///gives the inverse modulo any smaller power of two, e.g. `& MAX_INT` for 2^31.
+pub(crate) const fn inverse_pow2(n: u64) -> u64 {
+    // Every odd n is its own inverse modulo 8, and each step doubles the correct bits.
+    let mut inv = n;
+    let mut i = 0;
+    while i < 5 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(n.wrapping_mul(inv)));
+        i += 1;
+    }
+    inv
+}
+
+///Reverses the order of the low `ID_BITS` bits of n, dropping any bits above them.
+fn reverse_id_bits(n: u64) -> u64 {
+    (n & MAX_INT).reverse_bits() >> (u64::BITS - ID_BITS)
+}
+
+pub(crate) fn check_range(value: u64) -> Result<(), OptimusError> {
+    if value > MAX_INT {
+        return Err(OptimusError::InputOutOfRange {
+            value,
+            max: MAX_INT,
+        });
+    }
+    Ok(())
+}
 
 ///Optimus is used to encode and decode integers using Knuth's Hashing Algorithm.
-#[derive(Debug, Clone, Copy)]
+///
+///Optimus implements `Eq` and `Hash` over all three fields so configs can be used as
+///`HashMap` keys, e.g. for a cache of prepared encoders. The hash is computed from the
+///secret values, so only use it with hashers that are not exposed to an adversary,
+///such as the default `HashMap` hasher for an internal cache.
+///
+///With the `arbitrary` or `proptest` feature, Optimus implements that crate's `Arbitrary`
+///trait for fuzzing and property tests. Generated configs come from `from_seed`, so they
+///always satisfy `validate()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Optimus {
-    prime: u64,
-    mod_inverse: u64,
-    random: u64,
+    pub(crate) prime: u64,
+    pub(crate) mod_inverse: u64,
+    pub(crate) random: u64,
 }
 
 impl Optimus {
+    ///The largest id that can be encoded and the largest value encode produces.
+    ///This is the same value as the module level `MAX_INT`.
+    pub const MAX_INT: u64 = MAX_INT;
     /// Returns an Optimus struct that can be used to encode and decode integers.
     /// A common use case is for obfuscating internal ids of database primary keys.
     /// It is imperative that you keep a record of prime, modInverse and random so that
@@ -31,16 +108,305 @@ impl Optimus {
             random,
         })
     }
+    ///Returns an Optimus struct after running every check this crate has, and is the
+    ///recommended constructor for config from an untrusted source. In order, it checks that:
+    ///
+    ///- prime is prime (`OptimusError::NotPrime`)
+    ///- prime is no greater than `MAX_INT` (`OptimusError::PrimeOutOfRange`)
+    ///- random is no greater than `MAX_INT` (`OptimusError::RandomOutOfRange`)
+    ///- `(prime * mod_inverse) & MAX_INT == 1` (`OptimusError::InvalidModInverse`)
+    ///- prime is at least `MIN_STRICT_PRIME` (`OptimusError::DegeneratePrime`)
+    ///
+    ///`new` only checks the first of these and is kept as it is for compatibility. A future
+    ///major version could make `new` behave like `new_strict`.
+    /// # Errors
+    ///
+    /// Will return the `OptimusError` of the first check that fails
+    pub fn new_strict(prime: u64, mod_inverse: u64, random: u64) -> Result<Self, OptimusError> {
+        let optimus = Self::new_unchecked(prime, mod_inverse, random);
+        optimus.validate()?;
+        if prime < MIN_STRICT_PRIME {
+            return Err(OptimusError::DegeneratePrime {
+                value: prime,
+                min: MIN_STRICT_PRIME,
+            });
+        }
+        Ok(optimus)
+    }
+    ///Returns an Optimus struct without validating any of its arguments.
+    ///
+    ///CAUTION: an invalid config will silently fail to decode what it encodes. Prefer `new`,
+    ///or call `validate` on the result.
+    #[must_use]
+    pub const fn new_unchecked(prime: u64, mod_inverse: u64, random: u64) -> Self {
+        Self {
+            prime,
+            mod_inverse,
+            random,
+        }
+    }
+    ///Checks prime, modInverse and random independently and returns every reason they
+    ///can not be used together, or an empty Vec if they are valid. Unlike `new`, which
+    ///stops at the first problem, this is meant for config validation UIs that want to
+    ///report all problems at once.
+    ///
+    ///The checks are that prime is prime, that prime and random are no greater than
+    ///`MAX_INT`, and that `(prime * mod_inverse) & MAX_INT == 1`.
+    #[must_use]
+    pub fn diagnose(prime: u64, mod_inverse: u64, random: u64) -> Vec<OptimusError> {
+        let mut errors = vec![];
+        if !primal_check::miller_rabin(prime) {
+            errors.push(OptimusError::NotPrime);
+        }
+        if prime > MAX_INT {
+            errors.push(OptimusError::PrimeOutOfRange {
+                value: prime,
+                max: MAX_INT,
+            });
+        }
+        if random > MAX_INT {
+            errors.push(OptimusError::RandomOutOfRange {
+                value: random,
+                max: MAX_INT,
+            });
+        }
+        if !Self::new_unchecked(prime, mod_inverse, random).verify_inverse_relationship() {
+            errors.push(OptimusError::InvalidModInverse { prime, mod_inverse });
+        }
+        errors
+    }
+    ///Returns true if `(prime * mod_inverse) & MAX_INT == 1`, i.e. decode undoes encode.
+    ///This is the invariant every other check exists to protect, and is the same check
+    ///`validate` uses to report `OptimusError::InvalidModInverse`.
+    #[must_use]
+    pub fn verify_inverse_relationship(&self) -> bool {
+        self.prime.wrapping_mul(self.mod_inverse) & MAX_INT == 1
+    }
+    ///Checks this config with the same rules as `diagnose` and returns the first problem.
+    /// # Errors
+    ///
+    /// Will return the first `OptimusError` reported by `diagnose`
+    pub fn validate(&self) -> Result<(), OptimusError> {
+        Self::diagnose(self.prime, self.mod_inverse, self.random)
+            .into_iter()
+            .next()
+            .map_or(Ok(()), Err)
+    }
+    ///Validates a set of named configs, such as a `tenant -> config` map, and returns the
+    ///name and error of every config that fails `validate`, so a deployment can be rejected
+    ///at startup. A config that shares its prime with an earlier one is also reported, as
+    ///`OptimusError::SharedPrime` naming the earlier config.
+    pub fn validate_all<'a>(
+        configs: impl IntoIterator<Item = (&'a str, Optimus)>,
+    ) -> Vec<(String, OptimusError)> {
+        let mut seen: Vec<(&str, Optimus)> = vec![];
+        let mut errors = vec![];
+        for (name, config) in configs {
+            if let Err(e) = config.validate() {
+                errors.push((name.to_string(), e));
+            }
+            if let Some((other, _)) = seen.iter().find(|(_, o)| o.shares_prime(&config)) {
+                errors.push((
+                    name.to_string(),
+                    OptimusError::SharedPrime {
+                        other: (*other).to_string(),
+                    },
+                ));
+            }
+            seen.push((name, config));
+        }
+        errors
+    }
+    ///Returns a copy of this config with random replaced by `new_random`. Rotating only the
+    ///salt changes every encoded id without having to pick a new prime or recompute its
+    ///mod inverse.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::RandomOutOfRange` if `new_random` is greater than `MAX_INT`
+    pub fn with_random(&self, new_random: u64) -> Result<Optimus, OptimusError> {
+        if new_random > MAX_INT {
+            return Err(OptimusError::RandomOutOfRange {
+                value: new_random,
+                max: MAX_INT,
+            });
+        }
+        Ok(Self {
+            random: new_random,
+            ..*self
+        })
+    }
+    ///Returns true if both configs encode the same inputs to the same outputs, which unlike
+    ///`==` compares behaviour rather than stored fields. The boundaries `0`, `1`,
+    ///`MAX_INT - 1` and `MAX_INT` are always compared, followed by `samples` pseudo-random
+    ///inputs.
+    ///
+    ///NOTE: this is a probabilistic check unless samples is at least `MAX_INT + 1`, in which
+    ///case every input in the id space is compared instead.
+    #[must_use]
+    pub fn encodes_same_as(&self, other: &Optimus, samples: usize) -> bool {
+        let (a, b) = (self.raw(), other.raw());
+        let same = |n: u64| a.encode(n) == b.encode(n);
+        if samples as u64 > MAX_INT {
+            return (0..=MAX_INT).all(same);
+        }
+        let mut state = 0;
+        [0, 1, MAX_INT - 1, MAX_INT]
+            .into_iter()
+            .chain((0..samples).map(|_| splitmix64(&mut state) & MAX_INT))
+            .all(same)
+    }
+    ///Encodes every id and xors the results together into one value, e.g. as an `ETag` for a
+    ///page of results. xor is order independent, so any permutation of ids gives the same
+    ///value, and it allocates nothing.
+    ///
+    ///CAUTION: this is meant for change detection in caches, not security. Collisions are
+    ///easy by design: an id that appears twice cancels itself out, and so does the empty
+    ///slice, which combines to 0.
+    #[must_use]
+    pub fn encode_combined(&self, ids: &[u64]) -> u64 {
+        telemetry::record_batch(ids.len());
+        ids.iter().fold(0, |acc, &id| acc ^ self.encode(id))
+    }
+    ///Encodes every id, in order.
+    #[must_use]
+    pub fn encode_slice(&self, ids: &[u64]) -> Vec<u64> {
+        telemetry::record_batch(ids.len());
+        ids.iter().map(|&id| self.encode(id)).collect()
+    }
+    ///Encodes ids in pieces of chunk ids, calling f with each encoded piece, so a very large
+    ///input can be checkpointed, yielded or abandoned between chunks. Only one chunk is
+    ///held at a time, and the concatenated pieces equal `encode_slice(ids)`.
+    /// # Panics
+    ///
+    /// Will panic if chunk is 0
+    pub fn encode_chunked(&self, ids: &[u64], chunk: usize, mut f: impl FnMut(&[u64])) {
+        assert!(chunk > 0, "chunk size must be greater than 0");
+        telemetry::record_batch(ids.len());
+        let mut out = Vec::with_capacity(chunk.min(ids.len()));
+        for piece in ids.chunks(chunk) {
+            out.clear();
+            out.extend(piece.iter().map(|&id| self.encode(id)));
+            f(&out);
+        }
+    }
+    ///Checks a re-encoding from old to new, e.g. after rotating a table's config, and
+    ///returns the indices of the `(old_encoded, new_encoded)` pairs that do not decode to the
+    ///same id under their configs. A value above `MAX_INT` on either side is always
+    ///reported, as it is not a valid encoding.
+    #[must_use]
+    pub fn migration_check(old: &Optimus, new: &Optimus, pairs: &[(u64, u64)]) -> Vec<usize> {
+        pairs
+            .iter()
+            .enumerate()
+            .filter(|&(_, &(o, n))| {
+                o > MAX_INT || n > MAX_INT || old.raw().decode(o) != new.raw().decode(n)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+    ///Encodes input with every config and maps each output to the indices of the configs
+    ///that produce it, for auditing a fleet of tenant configs. Any entry with more than one
+    ///index is a collision: those tenants share the obfuscated form of input.
+    #[must_use]
+    pub fn output_collisions(configs: &[Optimus], input: u64) -> HashMap<u64, Vec<usize>> {
+        let mut outputs: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, config) in configs.iter().enumerate() {
+            outputs
+                .entry(config.raw().encode(input))
+                .or_default()
+                .push(i);
+        }
+        outputs
+    }
+    ///Returns the number of distinct ids in the space encode permutes, `MAX_INT + 1` (2^31).
+    #[must_use]
+    pub fn space_size(&self) -> u64 {
+        MAX_INT + 1
+    }
+    ///Returns every id whose encoded value falls in `output_range`, in order of the encoded
+    ///value, for debugging where a range of outputs came from. Ids in a range of outputs
+    ///are scattered over the whole id space, so this decodes each value of the range; the
+    ///cost is one decode per value in `output_range`, and the part above `MAX_INT` is
+    ///skipped as no id encodes to it.
+    pub fn inputs_mapping_to(&self, output_range: Range<u64>) -> impl Iterator<Item = u64> + '_ {
+        let end = output_range.end.min(MAX_INT + 1);
+        let raw = self.raw();
+        (output_range.start..end).map(move |e| raw.decode(e))
+    }
+    ///Returns the smallest and largest values encode can produce, `(0, MAX_INT)`, as encode
+    ///is a permutation of `0..=MAX_INT`. In base62 that is `"0"` to `"2LKcb1"`, so
+    ///`encode_base62` output is 1 to 6 characters wide.
+    #[must_use]
+    pub fn output_range(&self) -> (u64, u64) {
+        (0, MAX_INT)
+    }
+    ///Returns true if the multiplier is odd, which makes encode a bijection over the id space
+    ///`0..=MAX_INT` (an even multiplier has no inverse modulo 2^31). This checks a structural
+    ///property of the config without revealing any of its values.
+    #[must_use]
+    pub fn is_full_period(&self) -> bool {
+        self.prime % 2 == 1
+    }
+    ///Returns true if the prime used as the multiplier is odd, which it must be to be
+    ///invertible modulo 2^31. Same as `is_full_period`, named after the check itself.
+    #[must_use]
+    pub fn is_odd_multiplier(&self) -> bool {
+        self.is_full_period()
+    }
+    ///Returns true if this config is close to not obfuscating at all, so a UI can warn
+    ///about a prime that is valid but a bad choice. That is the case when prime is 1 modulo
+    ///2^31, which makes encode a plain xor with random, or when most of a sample of
+    ///pseudo-random ids encode to within 2^16 of themselves.
+    #[must_use]
+    pub fn is_identity_like(&self) -> bool {
+        const SAMPLES: usize = 64;
+        const DELTA: u64 = 1 << 16;
+        if self.prime & MAX_INT == 1 {
+            return true;
+        }
+        let raw = self.raw();
+        let mut state = 0;
+        let close = (0..SAMPLES)
+            .map(|_| splitmix64(&mut state) & MAX_INT)
+            .filter(|&n| raw.encode(n).abs_diff(n) <= DELTA)
+            .count();
+        close > SAMPLES / 2
+    }
+    ///Collects the quality checks on this config into one `StrengthReport`, e.g. for an
+    ///admin UI. A config is only as strong as the weakest field of the report.
+    #[must_use]
+    pub fn strength_report(&self) -> StrengthReport {
+        StrengthReport {
+            odd_multiplier: self.is_odd_multiplier(),
+            prime_large_enough: self.prime >= MIN_STRICT_PRIME,
+            random_non_degenerate: self.random != 0 && self.random != MAX_INT,
+        }
+    }
+    ///Returns true if both configs use the same prime.
+    #[must_use]
+    pub fn shares_prime(&self, other: &Optimus) -> bool {
+        self.prime == other.prime
+    }
     ///Returns an Optimus struct that can be used to encode and decode integers.
     ///random must be an integer less than `MAX_INT`.
     ///It automatically calculates prime's mod inverse and then calls new.
     /// # Errors
     ///
     /// Will return `OptimusError` if the argument `prime` is not prime
-    /// or if a Mod Inverse cannot be found
+    /// or if a Mod Inverse cannot be found, or `OptimusError::PrimeOutOfRange` if prime
+    /// is greater than `MAX_INT`
     ///
     pub fn new_calculated(prime: u64, random: u64) -> Result<Self, OptimusError> {
-        Self::new(prime, Self::calc_mod_inverse(prime as i64)?, random)
+        Self::new(prime, Self::calc_mod_inverse_u64(prime)?, random)
+    }
+    ///Returns this config with modInverse recalculated from prime, keeping prime and
+    ///random, for fixing a config that was stored with a wrong inverse. It is
+    ///`new_calculated(self.prime(), self.random())`.
+    /// # Errors
+    ///
+    /// Will return the errors of `new_calculated`, e.g. if prime is not prime
+    pub fn repair(&self) -> Result<Optimus, OptimusError> {
+        Self::new_calculated(self.prime, self.random)
     }
     ///returns the modular inverse of a given prime number.
     ///The modular inverse is defined such that
@@ -50,33 +416,889 @@ impl Optimus {
     ///
     ///NOTE: prime is assumed to be a valid prime. If prime is outside the bounds of
     ///an i64, then the function panics as it can not calculate the mod inverse.
+    ///
+    ///Deprecated: prefer `calc_mod_inverse_u64`, which takes the prime as a u64 and
+    ///rejects out of range primes with an error instead.
     /// # Errors
-    /// Will return `OptimusError` if the argument `prime` is not prime
-    /// or if a mod inverse cannot be found
+    /// Will return `OptimusError` if the argument `prime` is not prime,
+    /// `OptimusError::NoModInverse` if prime is 2, or `OptimusError::UnexpectedNoInverse`
+    /// if no inverse is found for an odd prime, which every odd prime has
     ///
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
     pub fn calc_mod_inverse(prime: i64) -> Result<u64, OptimusError> {
         const MAX: i64 = (MAX_INT + 1) as i64;
         if !primal_check::miller_rabin(prime as u64) {
             return Err(OptimusError::NotPrime);
         }
-        Ok(modinverse::modinverse(prime, MAX).ok_or(OptimusError::NoModInverse)? as u64)
+        match modinverse::modinverse(prime, MAX) {
+            Some(inverse) => Ok(inverse as u64),
+            // 2 is the only even prime and the only one without an inverse modulo 2^31.
+            None if prime % 2 == 0 => Err(OptimusError::NoModInverse),
+            None => Err(OptimusError::UnexpectedNoInverse {
+                prime: prime as u64,
+            }),
+        }
+    }
+    ///returns the modular inverse of a given prime number, like `calc_mod_inverse`, but
+    ///takes the prime as a u64 and never panics.
+    /// # Errors
+    /// Will return `OptimusError::PrimeOutOfRange` if prime is greater than `MAX_INT`,
+    /// `OptimusError::NotPrime` if it is not prime, or the errors of `calc_mod_inverse`
+    /// if a mod inverse cannot be found
+    ///
+    pub fn calc_mod_inverse_u64(prime: u64) -> Result<u64, OptimusError> {
+        let prime = i64::try_from(prime)
+            .ok()
+            .filter(|_| prime <= MAX_INT)
+            .ok_or(OptimusError::PrimeOutOfRange {
+                value: prime,
+                max: MAX_INT,
+            })?;
+        Self::calc_mod_inverse(prime)
+    }
+    ///Returns the modular inverse of prime modulo an arbitrary modulus, for experimenting
+    ///with id spaces that are not a power of two. A prime sharing a factor with modulus,
+    ///e.g. 3 modulo 15, has no inverse and is reported as such rather than as a generic
+    ///missing inverse. That includes 2 modulo 2^31, which `calc_mod_inverse` keeps reporting
+    ///as `OptimusError::NoModInverse`, the error existing callers already match on.
+    /// # Errors
+    /// Will return `OptimusError::NotPrime` if prime is not prime, or
+    /// `OptimusError::NotCoprime` if prime divides modulus
+    pub fn calc_mod_inverse_mod(prime: u64, modulus: u64) -> Result<u64, OptimusError> {
+        if !primal_check::miller_rabin(prime) {
+            return Err(OptimusError::NotPrime);
+        }
+        modinverse::modinverse(i128::from(prime), i128::from(modulus))
+            .and_then(|inverse| u64::try_from(inverse).ok())
+            .ok_or(OptimusError::NotCoprime { prime, modulus })
+    }
+    ///Returns a short statement of what Optimus does and does not protect against.
+    #[must_use]
+    pub fn security_note() -> &'static str {
+        "Optimus obfuscates ids, it does not encrypt them. The transform is linear: anyone \
+         who learns a few (id, encoded) pairs can recover the whole config, and from it \
+         every id. Use it to avoid exposing sequential ids, not to protect secrets or to \
+         authenticate requests."
+    }
+    ///Returns how many known (id, encoded) pairs are enough to recover prime, modInverse
+    ///and random. Working up from the lowest bit, each bit of an encoded value depends only
+    ///on the same and lower bits of the id, prime and random. So one pair with an even id
+    ///and one with an odd id pin down every bit of prime and random, and modInverse follows
+    ///from prime.
+    #[must_use]
+    pub fn pairs_to_break() -> u32 {
+        2
+    }
+    ///Returns `ALGORITHM_VERSION`, the version of the math this config encodes with.
+    #[must_use]
+    pub fn algorithm_version(&self) -> u32 {
+        ALGORITHM_VERSION
+    }
+    ///Returns the prime used to encode.
+    #[must_use]
+    pub fn prime(&self) -> u64 {
+        self.prime
+    }
+    ///Returns the mod inverse of prime used to decode.
+    #[must_use]
+    pub fn mod_inverse(&self) -> u64 {
+        self.mod_inverse
+    }
+    ///Returns the random value xored into every encoded id.
+    #[must_use]
+    pub fn random(&self) -> u64 {
+        self.random
     }
     ///Encodes n using Knuth's hashing algorithm.
+    #[must_use]
     pub fn encode(&self, n: u64) -> u64 {
-        ((n * self.prime) & MAX_INT) ^ self.random
+        telemetry::record_encode();
+        self.raw().encode(n)
+    }
+    ///Encodes any integer that converts to u64, checking both the conversion and that the
+    ///value is no greater than `MAX_INT`. This is the safest entry point for untrusted
+    ///input of arbitrary type.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::ConversionOverflow` if n does not fit in a u64, e.g. it is
+    /// negative, or `OptimusError::InputOutOfRange` if it is greater than `MAX_INT`
+    pub fn encode_checked<T: TryInto<u64>>(&self, n: T) -> Result<u64, OptimusError> {
+        let n = n.try_into().map_err(|_| OptimusError::ConversionOverflow)?;
+        check_range(n)?;
+        Ok(self.encode(n))
+    }
+    ///Encodes n like `encode` but as a `const fn`, so ids can be obfuscated at compile
+    ///time. Combine it with `new_unchecked`, which is also `const`:
+    ///
+    ///```
+    ///use optimus::Optimus;
+    ///const OPT: Optimus = Optimus::new_unchecked(1580030173, 59260789, 1163945558);
+    ///const HIDDEN: u64 = OPT.const_encode(15);
+    ///assert_eq!(HIDDEN, 1103647397);
+    ///```
+    ///Unlike `encode` it is not counted by the `metrics` feature, and it only takes u64.
+    #[must_use]
+    pub const fn const_encode(&self, n: u64) -> u64 {
+        RawOptimus::new_unchecked(self.prime, self.mod_inverse, self.random).encode(n)
+    }
+    ///Decodes n like `decode` but as a `const fn`. See `const_encode`.
+    #[must_use]
+    pub const fn const_decode(&self, n: u64) -> u64 {
+        RawOptimus::new_unchecked(self.prime, self.mod_inverse, self.random).decode(n)
+    }
+    ///Returns a closure that encodes its argument with a copy of this config, for use in
+    ///iterator chains. The closure is `Copy` and does not borrow self.
+    ///
+    ///```
+    ///use optimus::Optimus;
+    ///let opt = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+    ///let ids = [15, 16, 17];
+    ///let encoded: Vec<u64> = ids.iter().copied().map(opt.encoder()).collect();
+    ///let decoded: Vec<u64> = encoded.iter().copied().map(opt.decoder()).collect();
+    ///assert_eq!(encoded[0], 1103647397);
+    ///assert_eq!(decoded, ids);
+    ///```
+    pub fn encoder(&self) -> impl Fn(u64) -> u64 + Copy {
+        let optimus = *self;
+        move |n| optimus.encode(n)
+    }
+    ///Returns a closure that decodes its argument with a copy of this config. See `encoder`.
+    pub fn decoder(&self) -> impl Fn(u64) -> u64 + Copy {
+        let optimus = *self;
+        move |n| optimus.decode(n)
+    }
+    ///Encodes n and passes the result to f, for map-heavy pipelines.
+    #[must_use]
+    pub fn encode_map<T>(&self, n: u64, f: impl FnOnce(u64) -> T) -> T {
+        f(self.encode(n))
+    }
+    ///Decodes e and passes the result to f, e.g. to look up the decoded id.
+    ///
+    ///```
+    ///use optimus::Optimus;
+    ///let opt = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+    ///let names = ["zero", "one"];
+    ///let found: Vec<&str> = [opt.encode(1), opt.encode(0)]
+    ///    .into_iter()
+    ///    .map(|e| opt.decode_map(e, |id| names[id as usize]))
+    ///    .collect();
+    ///assert_eq!(found, ["one", "zero"]);
+    ///```
+    #[must_use]
+    pub fn decode_map<T>(&self, e: u64, f: impl FnOnce(u64) -> T) -> T {
+        f(self.decode(e))
+    }
+    ///Like `encode_map`, but checks that n is no greater than `MAX_INT` first.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if n is greater than `MAX_INT`, without
+    /// calling f
+    pub fn encode_map_checked<T>(
+        &self,
+        n: u64,
+        f: impl FnOnce(u64) -> T,
+    ) -> Result<T, OptimusError> {
+        check_range(n)?;
+        Ok(self.encode_map(n, f))
+    }
+    ///Like `decode_map`, but checks that e is no greater than `MAX_INT` first.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if e is greater than `MAX_INT`, without
+    /// calling f
+    pub fn decode_map_checked<T>(
+        &self,
+        e: u64,
+        f: impl FnOnce(u64) -> T,
+    ) -> Result<T, OptimusError> {
+        check_range(e)?;
+        Ok(self.decode_map(e, f))
+    }
+    ///Decodes e and rejects the result if it is above `real_max`, the largest id the
+    ///application has actually issued. Every value decodes to some id and most of them are
+    ///far above any real table size, so this turns away most forged or corrupted tokens
+    ///without a lookup.
+    ///
+    ///CAUTION: this is a heuristic gate, not authentication. A forged token has a
+    ///`real_max / MAX_INT` chance of landing on a plausible id.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if e is greater than `MAX_INT`, or
+    /// `OptimusError::DecodedOutOfExpectedRange` if the decoded id is greater than `real_max`
+    pub fn decode_bounded(&self, e: u64, real_max: u64) -> Result<u64, OptimusError> {
+        check_range(e)?;
+        let value = self.decode(e);
+        if value > real_max {
+            return Err(OptimusError::DecodedOutOfExpectedRange { value, real_max });
+        }
+        Ok(value)
+    }
+    ///Decodes e with this config and, if `is_valid` rejects the result, with legacy, for a
+    ///single config rotation. Both decodes always produce some number, so `is_valid`, e.g.
+    ///a lookup of the id, is what tells them apart. Returns the first decoded id that
+    ///`is_valid` accepts, or `None` if neither does.
+    pub fn decode_with_fallback(
+        &self,
+        e: u64,
+        legacy: &Optimus,
+        is_valid: impl Fn(u64) -> bool,
+    ) -> Option<u64> {
+        telemetry::record_decode();
+        [self, legacy]
+            .into_iter()
+            .map(|o| o.raw().decode(e))
+            .find(|&id| is_valid(id))
+    }
+    ///Returns true if e could have been produced by encode, i.e. is no greater than
+    ///`MAX_INT`. Every such value decodes to some id, so this only checks the form of e.
+    #[must_use]
+    pub fn is_valid_encoded(&self, e: u64) -> bool {
+        e <= MAX_INT
+    }
+    ///Splits tokens into those that pass `is_valid_encoded` and those that do not, in one
+    ///pass and keeping their order, so a bulk endpoint can process the first list and
+    ///report the second.
+    #[must_use]
+    pub fn filter_valid(&self, tokens: &[u64]) -> (Vec<u64>, Vec<u64>) {
+        tokens.iter().partition(|&&e| self.is_valid_encoded(e))
+    }
+    ///Decodes e and reports whether e was a valid encoded value, i.e. no greater than
+    ///`MAX_INT`, in a single call. This is meant for code that logs or audits every
+    ///decode attempt and wants a decoded value even for rejected input. Any u64 is
+    ///accepted: bits above `MAX_INT` are dropped before decoding.
+    ///
+    ///NOTE: `id` is only meaningful when `input_in_range` is true.
+    #[must_use]
+    pub fn decode_detailed(&self, e: u64) -> DecodeResult {
+        DecodeResult {
+            id: self.decode(e & MAX_INT),
+            input_in_range: e <= MAX_INT,
+        }
+    }
+    ///Resolves a leaked or reported encoded value to the original id, after checking this
+    ///config with `validate`. Any u64 is accepted, as with `decode_detailed`. The returned
+    ///`Resolution` records both values and whether encoded was in range, so it can be logged
+    ///as is during incident response.
+    /// # Errors
+    ///
+    /// Will return the first `OptimusError` reported by `validate`
+    pub fn resolve(&self, encoded: u64) -> Result<Resolution, OptimusError> {
+        self.validate()?;
+        let DecodeResult { id, input_in_range } = self.decode_detailed(encoded);
+        Ok(Resolution {
+            original: id,
+            encoded,
+            in_range: input_in_range,
+        })
+    }
+    ///Encodes n after reducing it modulo `MAX_INT + 1`, so every u64 has a defined encoding.
+    ///Inputs outside `0..=MAX_INT` wrap around the id space, which makes a negative sentinel
+    ///such as `(-1i64) as u64` encode the same as `MAX_INT`. decode returns the reduced value.
+    #[must_use]
+    pub fn encode_wrapping(&self, n: u64) -> u64 {
+        self.encode(n & MAX_INT)
     }
     ///Decodes n back to the original. It will only decode correctly if the Optimus struct
     ///is consistent with what was used to encode n.
+    #[must_use]
+    pub fn decode(&self, n: u64) -> u64 {
+        telemetry::record_decode();
+        self.raw().decode(n)
+    }
+    ///Decodes e as if it had been encoded with this config's prime and random masked to
+    ///`bits` bits instead of 31, for services handling ids from configs of several widths
+    ///during a migration. `try_decode_as(e, 31)` is the same as `decode(e)` for valid input.
+    ///
+    ///CAUTION: the width can not be recovered from the value itself. Every value that fits
+    ///in 31 bits is also a valid 63 bit value and decodes to a different id under each width,
+    ///so the caller has to know which width produced e. Tag tokens with a version, see
+    ///`encode_token`, rather than guessing from the magnitude.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InvalidWidth` if bits is not within `1..=64`, or
+    /// `OptimusError::InputOutOfRange` if e does not fit in bits bits
+    pub fn try_decode_as(&self, e: u64, bits: u32) -> Result<u64, OptimusError> {
+        let mask = Modulus::from_bits(bits)?.mask();
+        if e > mask {
+            return Err(OptimusError::InputOutOfRange {
+                value: e,
+                max: mask,
+            });
+        }
+        let mod_inverse = inverse_pow2(self.prime) & mask;
+        Ok(((e ^ self.random) & mask).wrapping_mul(mod_inverse) & mask)
+    }
+    ///Returns the config as an unvalidated `RawOptimus`.
+    #[must_use]
+    pub fn raw(&self) -> RawOptimus {
+        RawOptimus::new_unchecked(self.prime, self.mod_inverse, self.random)
+    }
+    ///Encodes n and returns it as an `i32`, for storage in signed 32 bit columns or
+    ///protobuf `int32` fields. Encoded ids are always within `0..=i32::MAX`.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if n is greater than `MAX_INT`, the same
+    /// as `i32::MAX`
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn encode_i32(&self, n: u32) -> Result<i32, OptimusError> {
+        let n = u64::from(n);
+        if n > MAX_INT {
+            return Err(OptimusError::InputOutOfRange {
+                value: n,
+                max: MAX_INT,
+            });
+        }
+        Ok((self.encode(n) & MAX_INT) as i32)
+    }
+    ///Decodes an id stored as an `i32` by `encode_i32`.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::NegativeInput` if n is negative, as no encoded id is
+    /// negative
+    pub fn decode_i32(&self, n: i32) -> Result<u64, OptimusError> {
+        let n = u64::try_from(n).map_err(|_| OptimusError::NegativeInput {
+            value: i64::from(n),
+        })?;
+        Ok(self.decode(n))
+    }
+    ///Encodes n and returns the result as 4 big-endian bytes, for `bytes` fields in binary
+    ///protocols. The top bit is always clear.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn encode_bytes(&self, n: u64) -> [u8; 4] {
+        ((self.encode(n) & MAX_INT) as u32).to_be_bytes()
+    }
+    ///Decodes 4 big-endian bytes produced by `encode_bytes`.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Parse` with `ParseErrorKind::InvalidLength` if b is not
+    /// exactly 4 bytes long, or
+    /// `OptimusError::InputOutOfRange` if they represent a value above `MAX_INT`
+    pub fn decode_bytes(&self, b: &[u8]) -> Result<u64, OptimusError> {
+        let bytes: [u8; 4] = b.try_into().map_err(|_| {
+            OptimusError::Parse(ParseErrorKind::InvalidLength {
+                expected: 4,
+                actual: b.len(),
+            })
+        })?;
+        let e = u64::from(u32::from_be_bytes(bytes));
+        check_range(e)?;
+        Ok(self.decode(e))
+    }
+    ///Encodes n with an order preserving transform, so that `a < b` implies
+    ///`encode_monotonic(a) < encode_monotonic(b)`. This is for callers that need range
+    ///queries or pagination over obfuscated ids, which `encode` does not support.
+    ///
+    ///The result is `n * prime + (random % prime)`, which is no longer within `MAX_INT`.
+    ///
+    ///CAUTION: this trades a lot of obfuscation strength for monotonicity. The difference
+    ///between any two outputs is a multiple of prime, so a handful of encoded values is
+    ///enough to recover prime and the relative order and spacing of ids is always visible.
+    ///
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if n is greater than `MAX_INT` or the
+    /// result does not fit in a u64, which only happens for a prime above `MAX_INT`, or
+    /// `OptimusError::NotPrime` if prime is 0
+    pub fn encode_monotonic(&self, n: u64) -> Result<u64, OptimusError> {
+        if n > MAX_INT {
+            return Err(OptimusError::InputOutOfRange {
+                value: n,
+                max: MAX_INT,
+            });
+        }
+        let offset = self.monotonic_offset()?;
+        n.checked_mul(self.prime)
+            .and_then(|m| m.checked_add(offset))
+            .ok_or(OptimusError::InputOutOfRange {
+                value: n,
+                max: (u64::MAX - offset) / self.prime,
+            })
+    }
+    ///Returns a secondary B-tree key for the row of id n that sorts in insertion order, so
+    ///storing encoded ids does not cost index locality. Store `encode(n)` as the public
+    ///column and `index_hint(n)` in an indexed column next to it, and order or paginate on
+    ///the hint. The hint is `encode_monotonic(n)`, so it does not show the raw id.
+    ///
+    ///CAUTION: the hint has the weaknesses of `encode_monotonic`, keep it out of responses.
+    ///
+    /// # Errors
+    ///
+    /// Will return the errors of `encode_monotonic`
+    pub fn index_hint(&self, n: u64) -> Result<u64, OptimusError> {
+        self.encode_monotonic(n)
+    }
+    ///Decodes a value produced by `encode_monotonic`.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InvalidEncoding` if e is not a possible output of
+    /// `encode_monotonic` for this config, `OptimusError::InputOutOfRange` if it
+    /// decodes to an id above `MAX_INT`, or `OptimusError::NotPrime` if prime is 0
+    pub fn decode_monotonic(&self, e: u64) -> Result<u64, OptimusError> {
+        let shifted = e
+            .checked_sub(self.monotonic_offset()?)
+            .ok_or(OptimusError::InvalidEncoding)?;
+        if shifted % self.prime != 0 {
+            return Err(OptimusError::InvalidEncoding);
+        }
+        let n = shifted / self.prime;
+        if n > MAX_INT {
+            return Err(OptimusError::InputOutOfRange {
+                value: n,
+                max: MAX_INT,
+            });
+        }
+        Ok(n)
+    }
+    ///Returns `random % prime`, the offset added by `encode_monotonic`.
+    fn monotonic_offset(&self) -> Result<u64, OptimusError> {
+        self.random
+            .checked_rem(self.prime)
+            .ok_or(OptimusError::NotPrime)
+    }
+    ///Packs two 15 bit values into a single 30 bit id and encodes it, which is useful
+    ///for composite keys such as `(tenant_id, row_id)`.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` with the offending value if `a` or `b`
+    /// does not fit in `PAIR_BITS` bits
+    pub fn encode_pair(&self, a: u16, b: u16) -> Result<u64, OptimusError> {
+        let (a, b) = (u64::from(a), u64::from(b));
+        if let Some(value) = [a, b].into_iter().find(|&v| v > PAIR_MASK) {
+            return Err(OptimusError::InputOutOfRange {
+                value,
+                max: PAIR_MASK,
+            });
+        }
+        Ok(self.encode((a << PAIR_BITS) | b))
+    }
+    ///Decodes n and unpacks the two values packed by `encode_pair`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn decode_pair(&self, n: u64) -> (u16, u16) {
+        let packed = self.decode(n);
+        (
+            ((packed >> PAIR_BITS) & PAIR_MASK) as u16,
+            (packed & PAIR_MASK) as u16,
+        )
+    }
+    ///Encodes n while passing its top `preserve_high_bits` bits (of the 31 bit id space)
+    ///through unchanged, obfuscating only the remaining low bits. This keeps e.g. a shard
+    ///prefix visible for routing while the rest of the id is hidden.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if n is greater than `MAX_INT`, or
+    /// `OptimusError::TooManyPreservedBits` if fewer than `MIN_OBFUSCATED_BITS` low bits
+    /// would be left to obfuscate
+    pub fn encode_low(&self, n: u64, preserve_high_bits: u32) -> Result<u64, OptimusError> {
+        let low_mask = Self::low_mask(n, preserve_high_bits)?;
+        let low = ((n & low_mask).wrapping_mul(self.prime) & low_mask) ^ (self.random & low_mask);
+        Ok((n & !low_mask) | low)
+    }
+    ///Decodes a value produced by `encode_low` with the same `preserve_high_bits`.
+    /// # Errors
+    ///
+    /// Will return the same errors as `encode_low`
+    pub fn decode_low(&self, n: u64, preserve_high_bits: u32) -> Result<u64, OptimusError> {
+        let low_mask = Self::low_mask(n, preserve_high_bits)?;
+        let low =
+            ((n & low_mask) ^ (self.random & low_mask)).wrapping_mul(self.mod_inverse) & low_mask;
+        Ok((n & !low_mask) | low)
+    }
+    fn low_mask(n: u64, preserve_high_bits: u32) -> Result<u64, OptimusError> {
+        const MAX_PRESERVED: u32 = ID_BITS - MIN_OBFUSCATED_BITS;
+        if n > MAX_INT {
+            return Err(OptimusError::InputOutOfRange {
+                value: n,
+                max: MAX_INT,
+            });
+        }
+        if preserve_high_bits > MAX_PRESERVED {
+            return Err(OptimusError::TooManyPreservedBits {
+                bits: preserve_high_bits,
+                max: MAX_PRESERVED,
+            });
+        }
+        Ok(MAX_INT >> preserve_high_bits)
+    }
+    ///Packs a timestamp and a sequence number into a single id and encodes it, for
+    ///snowflake style ids.
+    ///
+    ///The packed id is `seconds_since_epoch << SEQUENCE_BITS | seq`, i.e. 24 bits of
+    ///timestamp followed by 7 bits of sequence. 24 bits of seconds only covers about 194
+    ///days, so the epoch is not the Unix epoch but one you choose, such as the start of the
+    ///current deployment, and the same epoch must be used to interpret decoded timestamps.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InputOutOfRange` if `seconds_since_epoch` does not fit in
+    /// `TIMESTAMP_BITS` bits or `seq` does not fit in `SEQUENCE_BITS` bits
+    pub fn encode_timestamped(
+        &self,
+        seconds_since_epoch: u32,
+        seq: u16,
+    ) -> Result<u64, OptimusError> {
+        let (seconds, seq) = (u64::from(seconds_since_epoch), u64::from(seq));
+        for (value, bits) in [(seconds, TIMESTAMP_BITS), (seq, SEQUENCE_BITS)] {
+            let max = (1 << bits) - 1;
+            if value > max {
+                return Err(OptimusError::InputOutOfRange { value, max });
+            }
+        }
+        Ok(self.encode((seconds << SEQUENCE_BITS) | seq))
+    }
+    ///Decodes n and unpacks the timestamp and sequence number packed by
+    ///`encode_timestamped`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn decode_timestamped(&self, n: u64) -> (u32, u16) {
+        let packed = self.decode(n);
+        (
+            (packed >> SEQUENCE_BITS) as u32,
+            (packed & ((1 << SEQUENCE_BITS) - 1)) as u16,
+        )
+    }
+    ///Encodes n and then reverses the order of the 31 bits of the result, so the low bits of
+    ///n, which otherwise only affect the low bits of the output, end up at the top. The
+    ///result is still within `0..=MAX_INT` and `decode_scrambled` undoes both steps.
+    ///
+    ///CAUTION: this is still not cryptographic. The bit reversal is a fixed public
+    ///permutation, so undoing it first leaves the same linear scheme `pairs_to_break`
+    ///describes. It only hides patterns from casual inspection a little better.
+    #[must_use]
+    pub fn encode_scrambled(&self, n: u64) -> u64 {
+        reverse_id_bits(self.encode(n))
+    }
+    ///Decodes a value produced by `encode_scrambled`.
+    #[must_use]
+    pub fn decode_scrambled(&self, e: u64) -> u64 {
+        self.decode(reverse_id_bits(e))
+    }
+    ///Encodes n within the low 30 bits and sets `ENCODED_MARKER` on the result, so a value
+    ///that is encoded twice by mistake can be caught at runtime. Ids must be below
+    ///`ENCODED_MARKER`, half the usual range, and the 30 bit encoding differs from that of
+    ///`encode`. With `marker_bit_check` an input carrying the marker is reported as
+    ///`OptimusError::AlreadyEncoded`, otherwise as out of range.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::AlreadyEncoded` if `marker_bit_check` is set and n has
+    /// the marker, or `OptimusError::InputOutOfRange` if n is not below `ENCODED_MARKER`
+    pub fn encode_once(&self, n: u64, marker_bit_check: bool) -> Result<u64, OptimusError> {
+        let mask = ENCODED_MARKER - 1;
+        if marker_bit_check && n & !MAX_INT == 0 && n & ENCODED_MARKER != 0 {
+            return Err(OptimusError::AlreadyEncoded { value: n });
+        }
+        if n > mask {
+            return Err(OptimusError::InputOutOfRange {
+                value: n,
+                max: mask,
+            });
+        }
+        Ok(((n.wrapping_mul(self.prime) ^ self.random) & mask) | ENCODED_MARKER)
+    }
+    ///Decodes a value produced by `encode_once`, checking and clearing the marker.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::InvalidEncoding` if e does not carry `ENCODED_MARKER`, or
+    /// `OptimusError::InputOutOfRange` if e is greater than `MAX_INT`
+    pub fn decode_once(&self, e: u64) -> Result<u64, OptimusError> {
+        check_range(e)?;
+        if e & ENCODED_MARKER == 0 {
+            return Err(OptimusError::InvalidEncoding);
+        }
+        let mask = ENCODED_MARKER - 1;
+        Ok(((e ^ self.random) & mask).wrapping_mul(self.mod_inverse) & mask)
+    }
+    ///Encodes n leaving ids below `reserved_below`, such as well-known system accounts,
+    ///unchanged, and obfuscating the rest within `reserved_below..=MAX_INT`. The rest is
+    ///encoded by cycle-walking: encode is applied again until the result falls inside the
+    ///obfuscated part, which keeps it a bijection of that part. The walk only uses the low
+    ///31 bits of each parameter, so a random above `MAX_INT` cannot push it out of range.
+    ///
+    /// # Errors
+    ///
+    /// Will return `OptimusError::ReservedTooLarge` if `reserved_below` is greater than
+    /// `MAX_RESERVED`, `OptimusError::InvalidModInverse` if `verify_inverse_relationship` is
+    /// false, since the walk may then never end, or `OptimusError::InputOutOfRange` if n is
+    /// greater than `MAX_INT`
+    pub fn encode_partitioned(&self, n: u64, reserved_below: u64) -> Result<u64, OptimusError> {
+        telemetry::record_encode();
+        self.walk_partition(n, reserved_below, RawOptimus::encode)
+    }
+    ///Decodes a value produced by `encode_partitioned` with the same `reserved_below`.
+    /// # Errors
+    ///
+    /// Will return the same errors as `encode_partitioned`, for e instead of n
+    pub fn decode_partitioned(&self, e: u64, reserved_below: u64) -> Result<u64, OptimusError> {
+        telemetry::record_decode();
+        self.walk_partition(e, reserved_below, RawOptimus::decode)
+    }
+    ///Cycle-walks n with the raw arithmetic, so a call counts once in telemetry however
+    ///many steps it takes. Masks every parameter to `MAX_INT` so each step stays within
+    ///`0..=MAX_INT`, and requires the inverse relationship so each step is a bijection of it.
+    fn walk_partition(
+        &self,
+        n: u64,
+        reserved_below: u64,
+        step: fn(&RawOptimus, u64) -> u64,
+    ) -> Result<u64, OptimusError> {
+        if reserved_below > MAX_RESERVED {
+            return Err(OptimusError::ReservedTooLarge {
+                reserved_below,
+                max: MAX_RESERVED,
+            });
+        }
+        if !self.verify_inverse_relationship() {
+            return Err(OptimusError::InvalidModInverse {
+                prime: self.prime,
+                mod_inverse: self.mod_inverse,
+            });
+        }
+        check_range(n)?;
+        if n < reserved_below {
+            return Ok(n);
+        }
+        let raw = RawOptimus::new_unchecked(
+            self.prime & MAX_INT,
+            self.mod_inverse & MAX_INT,
+            self.random & MAX_INT,
+        );
+        let size = MAX_INT + 1 - reserved_below;
+        let mut m = step(&raw, n - reserved_below);
+        while m >= size {
+            m = step(&raw, m);
+        }
+        Ok(m + reserved_below)
+    }
+    ///Encodes n with random mixed with a time bucket, such as hours since the Unix epoch,
+    ///so the result only decodes back to n with `decode_time_bucketed` and the same bucket.
+    ///A service that accepts the current and the previous bucket gets coarse expiry for
+    ///tokens without storing them.
+    ///
+    ///CAUTION: this is obfuscation, not authentication. A token decoded with the wrong
+    ///bucket yields a different id rather than an error, so the decoded id still has to be
+    ///checked, e.g. by looking it up, and bucketed tokens are as easy to forge as any other.
+    ///n must be no greater than `MAX_INT`.
+    #[must_use]
+    pub fn encode_time_bucketed(&self, n: u64, bucket: u64) -> u64 {
+        self.mixed(bucket).encode(n)
+    }
+    ///Decodes a value produced by `encode_time_bucketed` with the same bucket.
+    #[must_use]
+    pub fn decode_time_bucketed(&self, e: u64, bucket: u64) -> u64 {
+        self.mixed(bucket).decode(e)
+    }
+    ///Encodes n with random adjusted by a hash of `salt_bytes`, binding the result to some
+    ///context such as a tenant key without creating a separate config. The same n and salt
+    ///always give the same output, and the result only decodes back to n with
+    ///`decode_salted` and the same salt.
+    ///
+    ///CAUTION: like `encode_time_bucketed`, decoding with the wrong salt yields a different
+    ///id rather than an error. n must be no greater than `MAX_INT`.
+    #[must_use]
+    pub fn encode_salted(&self, n: u64, salt_bytes: &[u8]) -> u64 {
+        self.mixed(fnv1a64(salt_bytes)).encode(n)
+    }
+    ///Decodes a value produced by `encode_salted` with the same salt.
+    #[must_use]
+    pub fn decode_salted(&self, e: u64, salt_bytes: &[u8]) -> u64 {
+        self.mixed(fnv1a64(salt_bytes)).decode(e)
+    }
+    ///Returns a copy with random xored with the splitmix64 output for value.
+    fn mixed(&self, value: u64) -> Self {
+        let mut state = value;
+        Self {
+            random: (self.random ^ splitmix64(&mut state)) & MAX_INT,
+            ..*self
+        }
+    }
+    ///Returns an `EncodeKey` holding only prime and random, which is all encode uses, for a
+    ///service that mints ids but never resolves them.
+    #[must_use]
+    pub fn encode_key(&self) -> EncodeKey {
+        EncodeKey::new(self.prime, self.random)
+    }
+    ///Returns a `DecodeKey` holding only modInverse and random, which is all decode uses,
+    ///for a service that resolves ids but never mints them.
+    #[must_use]
+    pub fn decode_key(&self) -> DecodeKey {
+        DecodeKey::new(self.mod_inverse, self.random)
+    }
+    ///Returns an `Inverse` whose `encode` behaves like this struct's `decode` and whose
+    ///`decode` behaves like this struct's `encode`, for setups where the mint and resolve
+    ///roles are swapped between services.
+    ///
+    ///NOTE: the inverse can not be represented as another Optimus. `encode` multiplies and
+    ///then applies the xor, while `decode` applies the xor and then multiplies, and the two
+    ///operations do not commute, so no choice of prime, modInverse and random reproduces
+    ///the reversed order.
+    #[must_use]
+    pub fn inverse(&self) -> Inverse {
+        Inverse { optimus: *self }
+    }
+}
+
+///`DecodeResult` is returned by `Optimus::decode_detailed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeResult {
+    ///The decoded value. Only meaningful when `input_in_range` is true.
+    pub id: u64,
+    ///Whether the decoded input was no greater than `MAX_INT`.
+    pub input_in_range: bool,
+}
+
+///`Resolution` is returned by `Optimus::resolve`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Resolution {
+    ///The id encoded resolves to. Only meaningful when `in_range` is true.
+    pub original: u64,
+    ///The encoded value that was resolved.
+    pub encoded: u64,
+    ///Whether encoded was no greater than `MAX_INT`.
+    pub in_range: bool,
+}
+
+///`StrengthReport` is returned by `Optimus::strength_report`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StrengthReport {
+    ///Whether prime is odd, see `Optimus::is_odd_multiplier`.
+    pub odd_multiplier: bool,
+    ///Whether prime is at least `MIN_STRICT_PRIME`.
+    pub prime_large_enough: bool,
+    ///Whether random is neither 0 nor `MAX_INT`, either of which makes the xor step a
+    ///no-op or a plain bit flip.
+    pub random_non_degenerate: bool,
+}
+
+///Inverse swaps the encode and decode directions of an Optimus. See `Optimus::inverse`.
+#[derive(Debug, Clone, Copy)]
+pub struct Inverse {
+    optimus: Optimus,
+}
+
+impl Inverse {
+    ///Encodes n using the decode direction of the original Optimus.
+    #[must_use]
+    pub fn encode(&self, n: u64) -> u64 {
+        self.optimus.decode(n)
+    }
+    ///Decodes n using the encode direction of the original Optimus.
+    #[must_use]
+    pub fn decode(&self, n: u64) -> u64 {
+        self.optimus.encode(n)
+    }
+    ///Returns the original Optimus.
+    #[must_use]
+    pub fn inverse(&self) -> Optimus {
+        self.optimus
+    }
+}
+
+///`EncodeKey` encodes like an `Optimus` without knowing its modInverse. See
+///`Optimus::encode_key`.
+///
+///CAUTION: prime determines modInverse, so an encode key is still secret. Leaving modInverse
+///out keeps the config that resolves ids in one place, it does not make the key safe to
+///publish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodeKey {
+    prime: u64,
+    random: u64,
+}
+
+impl EncodeKey {
+    ///Returns an `EncodeKey` from the prime and random of a config.
+    #[must_use]
+    pub const fn new(prime: u64, random: u64) -> Self {
+        Self { prime, random }
+    }
+    ///Returns the prime used to encode.
+    #[must_use]
+    pub fn prime(&self) -> u64 {
+        self.prime
+    }
+    ///Returns the random number used to encode.
+    #[must_use]
+    pub fn random(&self) -> u64 {
+        self.random
+    }
+    ///Encodes n the same way as `Optimus::encode`.
+    #[must_use]
+    pub fn encode(&self, n: u64) -> u64 {
+        telemetry::record_encode();
+        (n.wrapping_mul(self.prime) & MAX_INT) ^ self.random
+    }
+}
+
+///`DecodeKey` decodes like an `Optimus` without knowing its prime. See `Optimus::decode_key`.
+///
+///CAUTION: modInverse determines prime, as `prime & MAX_INT` is the inverse of modInverse,
+///so a decode key is still secret. Leaving prime out keeps the config that mints ids in one
+///place, it does not make the key safe to publish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DecodeKey {
+    mod_inverse: u64,
+    random: u64,
+}
+
+impl DecodeKey {
+    ///Returns a `DecodeKey` from the modInverse and random of a config.
+    #[must_use]
+    pub const fn new(mod_inverse: u64, random: u64) -> Self {
+        Self {
+            mod_inverse,
+            random,
+        }
+    }
+    ///Returns the modInverse used to decode.
+    #[must_use]
+    pub fn mod_inverse(&self) -> u64 {
+        self.mod_inverse
+    }
+    ///Returns the random number used to decode.
+    #[must_use]
+    pub fn random(&self) -> u64 {
+        self.random
+    }
+    ///Decodes n the same way as `Optimus::decode`.
+    #[must_use]
     pub fn decode(&self, n: u64) -> u64 {
-        ((n ^ self.random) * self.mod_inverse) & MAX_INT
+        telemetry::record_decode();
+        ((n ^ self.random).wrapping_mul(self.mod_inverse)) & MAX_INT
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
 mod tests {
     use super::*;
     use rand::Rng;
+    /// Every id ever encoded depends on `MAX_INT`. Changing it breaks compatibility with all of
+    /// them, so this must only be updated deliberately.
     #[test]
+    fn test_max_int() {
+        const _: () = assert!(MAX_INT == i32::MAX as u64);
+        const _: () = assert!((MAX_INT + 1).is_power_of_two());
+        assert_eq!(Optimus::MAX_INT, MAX_INT);
+        assert_eq!(MAX_INT, 2147483647);
+    }
+    #[test]
+    fn test_repair() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let broken = Optimus::new_unchecked(1580030173, 59260788, 1163945558);
+        assert!(broken.validate().is_err());
+        let repaired = broken.repair().unwrap();
+        assert!(repaired.validate().is_ok());
+        assert_eq!(repaired, o);
+        assert_eq!(o.repair().unwrap(), o);
+        assert!(matches!(
+            Optimus::new_unchecked(1580030174, 1, 1163945558).repair(),
+            Err(OptimusError::NotPrime)
+        ));
+    }
+    #[test]
+    #[allow(clippy::uninlined_format_args)]
     fn test_calc_mod_inverse() {
         let prime = 309779747;
         let expected_mod_inverse = 49560203;
@@ -87,18 +1309,80 @@ mod tests {
             expected_mod_inverse, calculated
         );
     }
-    /// Tests if the encoding process correctly decodes the id back to the original
     #[test]
-    fn test_encode() {
-        let mut rng = rand::thread_rng();
-        let os = [
+    fn test_calc_mod_inverse_u64() {
+        assert_eq!(Optimus::calc_mod_inverse_u64(309779747).unwrap(), 49560203);
+        assert_eq!(Optimus::calc_mod_inverse_u64(1580030173).unwrap(), 59260789);
+        let largest_u64_prime = u64::MAX - 58;
+        assert!(matches!(
+            Optimus::calc_mod_inverse_u64(largest_u64_prime),
+            Err(OptimusError::PrimeOutOfRange { .. })
+        ));
+        assert!(matches!(
+            Optimus::new_calculated(largest_u64_prime, 0),
+            Err(OptimusError::PrimeOutOfRange { .. })
+        ));
+        assert!(matches!(
+            Optimus::calc_mod_inverse_u64(309779748),
+            Err(OptimusError::NotPrime)
+        ));
+    }
+    #[test]
+    fn test_calc_mod_inverse_mod() {
+        assert_eq!(
+            Optimus::calc_mod_inverse_mod(1580030173, MAX_INT + 1).unwrap(),
+            59260789
+        );
+        assert_eq!(Optimus::calc_mod_inverse_mod(7, 26).unwrap(), 15);
+        assert_eq!(
+            Optimus::calc_mod_inverse_mod(7, 1_000_000).unwrap() * 7 % 1_000_000,
+            1
+        );
+        assert!(matches!(
+            Optimus::calc_mod_inverse_mod(3, 15),
+            Err(OptimusError::NotCoprime {
+                prime: 3,
+                modulus: 15
+            })
+        ));
+        assert!(matches!(
+            Optimus::calc_mod_inverse_mod(2, MAX_INT + 1),
+            Err(OptimusError::NotCoprime { prime: 2, .. })
+        ));
+        assert!(matches!(
+            Optimus::calc_mod_inverse_mod(15, 26),
+            Err(OptimusError::NotPrime)
+        ));
+    }
+    /// 2 is prime but even, so it has no inverse modulo 2^31 and is the expected
+    /// `NoModInverse` case rather than `UnexpectedNoInverse`.
+    #[test]
+    fn test_calc_mod_inverse_two() {
+        assert!(matches!(
+            Optimus::calc_mod_inverse(2),
+            Err(OptimusError::NoModInverse)
+        ));
+        assert!(matches!(
+            Optimus::new_calculated(2, 0),
+            Err(OptimusError::NoModInverse)
+        ));
+    }
+    fn test_configs() -> [Optimus; 6] {
+        [
             Optimus::new(309779747, 49560203, 57733611).unwrap(),
             Optimus::new(684934207, 1505143743, 846034763).unwrap(),
             Optimus::new(743534599, 1356791223, 1336232185).unwrap(),
             Optimus::new(54661037, 1342843941, 576322863).unwrap(),
             Optimus::new(198194831, 229517423, 459462336).unwrap(),
             Optimus::new_calculated(198194831, 459462336).unwrap(),
-        ];
+        ]
+    }
+    /// Tests if the encoding process correctly decodes the id back to the original
+    #[test]
+    #[allow(clippy::uninlined_format_args, clippy::semicolon_if_nothing_returned)]
+    fn test_encode() {
+        let mut rng = rand::thread_rng();
+        let os = test_configs();
         println!("{:?}", os[1]);
         for o in os {
             let c = 10;
@@ -130,4 +1414,806 @@ mod tests {
             }
         }
     }
+    #[test]
+    fn test_output_range() {
+        for o in test_configs() {
+            assert_eq!(o.output_range(), (0, MAX_INT));
+            let (min, max) = o.output_range();
+            assert_eq!(o.encode(o.decode(min)), min);
+            assert_eq!(o.encode(o.decode(max)), max);
+        }
+    }
+    /// The range contract is inclusive: both ends of `0..=MAX_INT` round trip.
+    #[test]
+    fn test_boundaries() {
+        for o in test_configs() {
+            for n in [0, 1, MAX_INT - 1, MAX_INT] {
+                let e = o.encode(n);
+                assert!(e <= MAX_INT, "{o:?} encoded {n} to {e}");
+                assert_eq!(o.decode(e), n, "{o:?} did not round trip {n}");
+            }
+        }
+    }
+    #[test]
+    fn test_encode_pair() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        for (a, b) in [(0, 0), (1, 2), (42, 7), (0x7FFF, 0x7FFF), (0x7FFF, 0)] {
+            let e = o.encode_pair(a, b).unwrap();
+            assert!(e <= MAX_INT);
+            assert_eq!(o.decode_pair(e), (a, b));
+        }
+        assert!(matches!(
+            o.encode_pair(0x8000, 0),
+            Err(OptimusError::InputOutOfRange {
+                value: 0x8000,
+                max: 0x7FFF
+            })
+        ));
+        let err = o.encode_pair(0, u16::MAX).unwrap_err();
+        assert_eq!(err.to_string(), "input 65535 exceeds maximum 32767");
+    }
+    #[test]
+    fn test_diagnose() {
+        assert!(Optimus::diagnose(1580030173, 59260789, 1163945558).is_empty());
+        let errors = Optimus::diagnose(1580030174, 59260789, MAX_INT + 1);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(matches!(errors[0], OptimusError::NotPrime));
+        assert!(matches!(
+            errors[1],
+            OptimusError::RandomOutOfRange {
+                value: 2147483648,
+                max: MAX_INT
+            }
+        ));
+        assert!(matches!(
+            errors[2],
+            OptimusError::InvalidModInverse {
+                prime: 1580030174,
+                mod_inverse: 59260789
+            }
+        ));
+        let errors = Optimus::diagnose(4294967311, 1, 0);
+        assert!(matches!(
+            errors[..],
+            [
+                OptimusError::PrimeOutOfRange { .. },
+                OptimusError::InvalidModInverse { .. }
+            ]
+        ));
+    }
+    #[test]
+    fn test_validate_all() {
+        let good = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let bad_inverse = Optimus::new(309779747, 1, 57733611).unwrap();
+        let bad_random = Optimus::new(684934207, 1505143743, MAX_INT + 1).unwrap();
+        let same_prime = Optimus::new_calculated(1580030173, 1).unwrap();
+        assert!(good.validate().is_ok());
+        assert!(Optimus::validate_all([
+            ("a", good),
+            ("b", Optimus::new_calculated(54661037, 7).unwrap())
+        ])
+        .is_empty());
+        let errors = Optimus::validate_all([
+            ("a", good),
+            ("b", bad_inverse),
+            ("c", bad_random),
+            ("d", same_prime),
+        ]);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(matches!(&errors[0], (n, OptimusError::InvalidModInverse { .. }) if n == "b"));
+        assert!(matches!(&errors[1], (n, OptimusError::RandomOutOfRange { .. }) if n == "c"));
+        assert!(
+            matches!(&errors[2], (n, OptimusError::SharedPrime { other }) if n == "d" && other == "a")
+        );
+    }
+    #[test]
+    fn test_with_random() {
+        let old = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let new = old.with_random(57733611).unwrap();
+        assert!(new.shares_prime(&old));
+        for id in [0, 15, MAX_INT] {
+            assert_ne!(old.encode(id), new.encode(id));
+            assert_eq!(old.decode(old.encode(id)), id);
+            assert_eq!(new.decode(new.encode(id)), id);
+        }
+        assert!(matches!(
+            old.with_random(MAX_INT + 1),
+            Err(OptimusError::RandomOutOfRange { .. })
+        ));
+    }
+    #[test]
+    #[allow(clippy::cast_sign_loss)]
+    fn test_encode_wrapping() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.encode_wrapping(15), o.encode(15));
+        assert_eq!(o.encode_wrapping((-1i64) as u64), o.encode(MAX_INT));
+        assert_eq!(o.encode_wrapping(MAX_INT + 16), o.encode(15));
+        assert_eq!(o.decode(o.encode_wrapping(u64::MAX)), MAX_INT);
+    }
+    #[test]
+    fn test_encode_i32() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.encode_i32(15).unwrap(), 1103647397);
+        let e = o.encode_i32(u32::try_from(MAX_INT).unwrap()).unwrap();
+        assert!(e >= 0);
+        assert_eq!(o.decode_i32(e).unwrap(), MAX_INT);
+        let decoded = o.decode_i32(i32::MAX).unwrap();
+        assert_eq!(decoded, o.decode(MAX_INT));
+        assert_eq!(
+            o.encode_i32(u32::try_from(decoded).unwrap()).unwrap(),
+            i32::MAX
+        );
+        // i32::MAX + 1 would otherwise encode the same as 0.
+        assert!(matches!(
+            o.encode_i32(1 << 31),
+            Err(OptimusError::InputOutOfRange {
+                value: 2147483648,
+                ..
+            })
+        ));
+        assert!(matches!(
+            o.encode_i32(u32::MAX),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+        assert!(matches!(
+            o.decode_i32(-1),
+            Err(OptimusError::NegativeInput { value: -1 })
+        ));
+    }
+    #[test]
+    fn test_encode_monotonic() {
+        let mut rng = rand::thread_rng();
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let mut ids: Vec<u64> = (0..100).map(|_| rng.gen_range(0..=MAX_INT)).collect();
+        ids.extend([0, 1, MAX_INT - 1, MAX_INT]);
+        ids.sort_unstable();
+        ids.dedup();
+        for pair in ids.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!(
+                o.encode_monotonic(a).unwrap() < o.encode_monotonic(b).unwrap(),
+                "{a} {b}"
+            );
+        }
+        for id in ids {
+            let e = o.encode_monotonic(id).unwrap();
+            assert_eq!(o.decode_monotonic(e).unwrap(), id);
+        }
+        assert!(matches!(
+            o.decode_monotonic(o.encode_monotonic(15).unwrap() + 1),
+            Err(OptimusError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            o.decode_monotonic(0),
+            Err(OptimusError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            o.encode_monotonic(MAX_INT + 1),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+        let huge = Optimus::new(u64::MAX - 58, 1, 1163945558).unwrap();
+        assert!(matches!(
+            huge.encode_monotonic(2),
+            Err(OptimusError::InputOutOfRange { value: 2, max: 0 })
+        ));
+        // new_unchecked allows a prime of 0, which must not divide by zero.
+        let zero = Optimus::new_unchecked(0, 0, 1163945558);
+        assert!(matches!(
+            zero.encode_monotonic(15),
+            Err(OptimusError::NotPrime)
+        ));
+        assert!(matches!(
+            zero.decode_monotonic(15),
+            Err(OptimusError::NotPrime)
+        ));
+    }
+    #[test]
+    fn test_encodes_same_as() {
+        let a = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert!(a.encodes_same_as(&a, 1000));
+        assert!(a.encodes_same_as(
+            &Optimus::new_calculated(1580030173, 1163945558).unwrap(),
+            1000
+        ));
+        assert!(!a.encodes_same_as(&a.with_random(1).unwrap(), 1000));
+        assert!(!a.encodes_same_as(&Optimus::new(309779747, 49560203, 1163945558).unwrap(), 0));
+    }
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "encode overflow")]
+    fn test_encode_overflow_oversized_prime() {
+        let largest_u64_prime = u64::MAX - 58;
+        let o = Optimus::new_unchecked(largest_u64_prime, 1, 0);
+        let _ = o.encode(15);
+    }
+    #[test]
+    fn test_is_full_period() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert!(o.is_full_period());
+        assert_eq!(o.space_size(), 1 << 31);
+        assert!(!Optimus::new(2, 1, 0).unwrap().is_full_period());
+    }
+    /// The ids 0 and 1 make the recovery trivial: encode(0) is random and
+    /// encode(1) ^ random is prime.
+    #[test]
+    fn test_pairs_to_break() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let pairs: Vec<(u64, u64)> = (0..u64::from(Optimus::pairs_to_break()))
+            .map(|n| (n, o.encode(n)))
+            .collect();
+        let random = pairs[0].1;
+        let prime = pairs[1].1 ^ random;
+        let recovered = Optimus::new_calculated(prime, random).unwrap();
+        assert_eq!(recovered, o);
+        assert!(Optimus::security_note().contains("not encrypt"));
+    }
+    #[test]
+    fn test_new_strict() {
+        let o = Optimus::new_strict(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o, Optimus::new(1580030173, 59260789, 1163945558).unwrap());
+        let err = |r: Result<Optimus, OptimusError>| r.unwrap_err();
+        assert!(matches!(
+            err(Optimus::new_strict(1580030173, 1, 1163945558)),
+            OptimusError::InvalidModInverse { .. }
+        ));
+        assert!(matches!(
+            err(Optimus::new_strict(1580030173, 59260789, MAX_INT + 1)),
+            OptimusError::RandomOutOfRange { .. }
+        ));
+        let small = Optimus::calc_mod_inverse_u64(65521).unwrap();
+        assert!(Optimus::new(65521, small, 0).is_ok());
+        assert!(matches!(
+            err(Optimus::new_strict(65521, small, 0)),
+            OptimusError::DegeneratePrime { value: 65521, .. }
+        ));
+    }
+    #[test]
+    fn test_try_decode_as() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.try_decode_as(1103647397, ID_BITS).unwrap(), 15);
+        let wide = ((15 * o.prime) ^ o.random) & (u64::MAX >> 1);
+        assert_eq!(o.try_decode_as(wide, 63).unwrap(), 15);
+        assert_ne!(o.try_decode_as(1103647397, 63).unwrap(), 15);
+        assert!(matches!(
+            o.try_decode_as(MAX_INT + 1, ID_BITS),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+        for bits in [0, 65] {
+            assert!(matches!(
+                o.try_decode_as(15, bits),
+                Err(OptimusError::InvalidWidth { .. })
+            ));
+        }
+    }
+    #[test]
+    fn test_verify_inverse_relationship() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert!(o.verify_inverse_relationship());
+        assert!(
+            !Optimus::new_unchecked(1580030173, 59260790, 1163945558).verify_inverse_relationship()
+        );
+    }
+    #[test]
+    fn test_time_bucketed() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let hour = 482_000;
+        for id in [0, 15, MAX_INT] {
+            let e = o.encode_time_bucketed(id, hour);
+            assert!(e <= MAX_INT);
+            assert_eq!(o.decode_time_bucketed(e, hour), id);
+            assert_ne!(o.decode_time_bucketed(e, hour + 1), id);
+            assert_ne!(o.encode_time_bucketed(id, hour + 1), e);
+        }
+    }
+    #[test]
+    fn test_map() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.encode_map(15, |e| e + 1), 1103647398);
+        assert_eq!(o.decode_map(1103647397, |id| id * 2), 30);
+        assert_eq!(o.encode_map_checked(15, |e| e).unwrap(), 1103647397);
+        assert_eq!(o.decode_map_checked(1103647397, |id| id).unwrap(), 15);
+        let called = std::cell::Cell::new(false);
+        assert!(matches!(
+            o.decode_map_checked(MAX_INT + 1, |_| called.set(true)),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+        assert!(o.encode_map_checked(MAX_INT + 1, |_| ()).is_err());
+        assert!(!called.get());
+    }
+    /// 75161927681 is 35 * 2^31 + 1, a prime that passes `new` but multiplies by 1.
+    #[test]
+    fn test_is_identity_like() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert!(!o.is_identity_like());
+        let weak = Optimus::new_unchecked(75161927681, 1, 0);
+        assert!(weak.validate().is_err());
+        assert!(Optimus::new(75161927681, 1, 0).is_ok());
+        assert!(weak.is_identity_like());
+        assert!(Optimus::new_unchecked(75161927681, 1, 1163945558).is_identity_like());
+        assert!(Optimus::new_unchecked(1, 1, 40).is_identity_like());
+    }
+    #[test]
+    fn test_strength_report() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert!(o.is_odd_multiplier());
+        assert_eq!(
+            o.strength_report(),
+            StrengthReport {
+                odd_multiplier: true,
+                prime_large_enough: true,
+                random_non_degenerate: true,
+            }
+        );
+        let weak = Optimus::new_unchecked(7, 920350135, 0);
+        assert_eq!(
+            weak.strength_report(),
+            StrengthReport {
+                odd_multiplier: true,
+                prime_large_enough: false,
+                random_non_degenerate: false,
+            }
+        );
+        assert!(!Optimus::new_unchecked(2, 0, 1163945558).is_odd_multiplier());
+        assert!(
+            !Optimus::new_unchecked(1580030173, 59260789, MAX_INT)
+                .strength_report()
+                .random_non_degenerate
+        );
+    }
+    #[test]
+    fn test_bytes() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.encode_bytes(15), [0x41, 0xc8, 0x52, 0xa5]);
+        for id in [0, 15, MAX_INT] {
+            assert_eq!(o.decode_bytes(&o.encode_bytes(id)).unwrap(), id);
+        }
+        for len in [0, 3, 5] {
+            assert!(matches!(
+                o.decode_bytes(&vec![0; len]),
+                Err(OptimusError::Parse(ParseErrorKind::InvalidLength { expected: 4, actual }))
+                    if actual == len
+            ));
+        }
+        assert!(matches!(
+            o.decode_bytes(&[0x80, 0, 0, 0]),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+    }
+    #[test]
+    fn test_migration_check() {
+        let old = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let new = old.with_random(42).unwrap();
+        let mut pairs: Vec<(u64, u64)> = [0, 15, MAX_INT]
+            .into_iter()
+            .map(|id| (old.encode(id), new.encode(id)))
+            .collect();
+        assert!(Optimus::migration_check(&old, &new, &pairs).is_empty());
+        pairs.push((old.encode(16), new.encode(17)));
+        pairs.push((MAX_INT + 1, new.encode(1)));
+        assert_eq!(Optimus::migration_check(&old, &new, &pairs), [3, 4]);
+    }
+    /// Changing either assertion means every stored id changes: bump `ALGORITHM_VERSION`
+    /// and update both together.
+    #[test]
+    fn test_algorithm_version() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.algorithm_version(), 1);
+        assert_eq!(o.encode(15), 1103647397);
+    }
+    #[test]
+    fn test_decode_with_fallback() {
+        let legacy = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let current = legacy.with_random(42).unwrap();
+        let known = [15, 16];
+        let is_valid = |id| known.contains(&id);
+        let old_token = legacy.encode(15);
+        let new_token = current.encode(16);
+        assert_eq!(
+            current.decode_with_fallback(new_token, &legacy, is_valid),
+            Some(16)
+        );
+        assert_eq!(
+            current.decode_with_fallback(old_token, &legacy, is_valid),
+            Some(15)
+        );
+        assert_eq!(
+            current.decode_with_fallback(legacy.encode(17), &legacy, is_valid),
+            None
+        );
+    }
+    #[test]
+    fn test_encode_key() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let key = o.encode_key();
+        assert_eq!(key, EncodeKey::new(1580030173, 1163945558));
+        assert_eq!((key.prime(), key.random()), (1580030173, 1163945558));
+        assert_eq!(
+            std::mem::size_of::<EncodeKey>(),
+            2 * std::mem::size_of::<u64>()
+        );
+        for n in [0, 15, 1_000_000, MAX_INT] {
+            assert_eq!(key.encode(n), o.encode(n));
+        }
+        // encode never reads modInverse.
+        let wrong_inverse = Optimus::new_unchecked(1580030173, 1, 1163945558);
+        assert_eq!(wrong_inverse.encode(15), key.encode(15));
+    }
+    #[test]
+    fn test_decode_key() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let key = o.decode_key();
+        assert_eq!(key, DecodeKey::new(59260789, 1163945558));
+        assert_eq!((key.mod_inverse(), key.random()), (59260789, 1163945558));
+        for n in [0, 15, 1_000_000, MAX_INT] {
+            assert_eq!(key.decode(o.encode(n)), n);
+            assert_eq!(key.decode(n), o.decode(n));
+        }
+    }
+    #[test]
+    fn test_scrambled() {
+        assert_eq!(reverse_id_bits(1), 1 << 30);
+        assert_eq!(reverse_id_bits(MAX_INT), MAX_INT);
+        assert_eq!(reverse_id_bits(0), 0);
+        for o in test_configs() {
+            for n in [0, 1, 2, 15, MAX_INT - 1, MAX_INT] {
+                let e = o.encode_scrambled(n);
+                assert!(e <= MAX_INT);
+                assert_eq!(reverse_id_bits(e), o.encode(n));
+                assert_eq!(o.decode_scrambled(e), n);
+            }
+        }
+    }
+    #[test]
+    fn test_inputs_mapping_to() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let range = 1103647390..1103647400;
+        let inputs: Vec<u64> = o.inputs_mapping_to(range.clone()).collect();
+        assert_eq!(inputs.len(), 10);
+        assert!(inputs.contains(&15));
+        assert!(inputs.iter().all(|&n| range.contains(&o.encode(n))));
+        let top: Vec<u64> = o.inputs_mapping_to(MAX_INT - 1..u64::MAX).collect();
+        assert_eq!(top, [o.decode(MAX_INT - 1), o.decode(MAX_INT)]);
+        assert_eq!(o.inputs_mapping_to(0..0).count(), 0);
+    }
+    #[test]
+    fn test_encode_combined() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let ids = [3, 15, 42, 1_000_000];
+        let combined = o.encode_combined(&ids);
+        assert_eq!(o.encode_combined(&[1_000_000, 42, 3, 15]), combined);
+        assert_ne!(o.encode_combined(&ids[..3]), combined);
+        assert_eq!(o.encode_combined(&[15]), 1103647397);
+        assert_eq!(o.encode_combined(&[15, 15]), 0);
+        assert_eq!(o.encode_combined(&[]), 0);
+    }
+    #[test]
+    fn test_salted() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let salts: [&[u8]; 3] = [b"", b"tenant-a", b"tenant-b"];
+        for id in [0, 15, MAX_INT] {
+            for salt in salts {
+                let e = o.encode_salted(id, salt);
+                assert!(e <= MAX_INT);
+                assert_eq!(e, o.encode_salted(id, salt));
+                assert_eq!(o.decode_salted(e, salt), id);
+            }
+            let a = o.encode_salted(id, b"tenant-a");
+            assert_ne!(a, o.encode_salted(id, b"tenant-b"));
+            assert_ne!(o.decode_salted(a, b"tenant-b"), id);
+        }
+    }
+    #[test]
+    fn test_const_encode() {
+        const OPT: Optimus = Optimus::new_unchecked(1580030173, 59260789, 1163945558);
+        const HIDDEN: u64 = OPT.const_encode(15);
+        const _: () = assert!(HIDDEN == 1103647397);
+        const _: () = assert!(OPT.const_decode(HIDDEN) == 15);
+        assert_eq!(OPT.const_encode(MAX_INT), OPT.encode(MAX_INT));
+    }
+    #[test]
+    fn test_output_collisions() {
+        let a = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let b = Optimus::new(309779747, 49560203, 57733611).unwrap();
+        // Pick a random for c that makes it collide with a on id 15.
+        let c = Optimus::new_calculated(684934207, ((15 * 684934207) & MAX_INT) ^ a.encode(15))
+            .unwrap();
+        let configs = [a, b, c];
+        let outputs = Optimus::output_collisions(&configs, 15);
+        assert_eq!(outputs[&1103647397], [0, 2]);
+        assert_eq!(outputs[&b.encode(15)], [1]);
+        assert_eq!(outputs.len(), 2);
+        assert!(Optimus::output_collisions(&configs, 16)
+            .values()
+            .all(|v| v.len() == 1));
+    }
+    #[test]
+    fn test_decode_bounded() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let real_max = 10_000_000;
+        assert_eq!(o.decode_bounded(1103647397, real_max).unwrap(), 15);
+        let forged = 1103647398;
+        assert!(o.decode(forged) > real_max);
+        assert!(matches!(
+            o.decode_bounded(forged, real_max),
+            Err(OptimusError::DecodedOutOfExpectedRange {
+                real_max: 10_000_000,
+                ..
+            })
+        ));
+        assert!(matches!(
+            o.decode_bounded(MAX_INT + 1, real_max),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+    }
+    #[test]
+    fn test_encode_chunked() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let ids: Vec<u64> = (0..1000).map(|n| n * 7919).collect();
+        let whole = o.encode_slice(&ids);
+        assert_eq!(whole[15], o.encode(15 * 7919));
+        for chunk in [1, 7, 1000, 5000] {
+            let mut pieces = vec![];
+            let mut sizes = vec![];
+            o.encode_chunked(&ids, chunk, |piece| {
+                sizes.push(piece.len());
+                pieces.extend_from_slice(piece);
+            });
+            assert_eq!(pieces, whole);
+            assert!(sizes.iter().all(|&len| len <= chunk));
+            assert_eq!(sizes.len(), ids.len().div_ceil(chunk));
+        }
+        let mut calls = 0;
+        o.encode_chunked(&[], 10, |_| calls += 1);
+        assert_eq!(calls, 0);
+        assert!(o.encode_slice(&[]).is_empty());
+    }
+    #[test]
+    fn test_index_hint() {
+        for o in test_configs() {
+            let hints: Vec<u64> = (0..1000).map(|n| o.index_hint(n).unwrap()).collect();
+            assert!(hints.windows(2).all(|w| w[0] < w[1]), "{o:?}");
+            assert!(o.index_hint(MAX_INT - 1).unwrap() < o.index_hint(MAX_INT).unwrap());
+            assert_ne!(o.index_hint(15).unwrap(), 15);
+            assert!(matches!(
+                o.index_hint(MAX_INT + 1),
+                Err(OptimusError::InputOutOfRange { .. })
+            ));
+            assert!(o.index_hint(u64::MAX).is_err());
+        }
+    }
+    #[test]
+    fn test_encode_once() {
+        for o in test_configs() {
+            for n in [0, 1, 15, ENCODED_MARKER - 1] {
+                let e = o.encode_once(n, true).unwrap();
+                assert!(e & ENCODED_MARKER != 0 && e <= MAX_INT);
+                assert_eq!(o.decode_once(e).unwrap(), n);
+                assert!(matches!(
+                    o.encode_once(e, true),
+                    Err(OptimusError::AlreadyEncoded { value }) if value == e
+                ));
+                assert!(matches!(
+                    o.encode_once(e, false),
+                    Err(OptimusError::InputOutOfRange { .. })
+                ));
+            }
+        }
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert!(matches!(
+            o.decode_once(15),
+            Err(OptimusError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            o.encode_once(MAX_INT + 1, true),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+    }
+    #[test]
+    fn test_partitioned() {
+        for o in test_configs() {
+            for reserved in [0, 1, 1001, MAX_RESERVED] {
+                let edges = [
+                    0,
+                    reserved.saturating_sub(1),
+                    reserved,
+                    reserved + 1,
+                    MAX_INT,
+                ];
+                for n in edges.into_iter().chain(2000..2100) {
+                    let e = o.encode_partitioned(n, reserved).unwrap();
+                    if n < reserved {
+                        assert_eq!(e, n);
+                    } else {
+                        assert!((reserved..=MAX_INT).contains(&e), "{o:?} {n}");
+                    }
+                    assert_eq!(o.decode_partitioned(e, reserved).unwrap(), n);
+                }
+            }
+        }
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.encode_partitioned(15, 0).unwrap(), o.encode(15));
+        assert!(matches!(
+            o.encode_partitioned(15, MAX_RESERVED + 1),
+            Err(OptimusError::ReservedTooLarge { .. })
+        ));
+        assert!(matches!(
+            o.decode_partitioned(MAX_INT + 1, 1001),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+        // A random above MAX_INT used to leave every step out of range, so the walk never
+        // ended.
+        let high = Optimus::new(1580030173, 59260789, 1 << 31).unwrap();
+        let e = high.encode_partitioned(2000, 1000).unwrap();
+        assert!((1000..=MAX_INT).contains(&e));
+        assert_eq!(high.decode_partitioned(e, 1000).unwrap(), 2000);
+        let broken = Optimus::new(1580030173, 59260788, 1163945558).unwrap();
+        assert!(matches!(
+            broken.encode_partitioned(2000, 1000),
+            Err(OptimusError::InvalidModInverse { .. })
+        ));
+    }
+    #[test]
+    fn test_encode_checked() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.encode_checked(15u32).unwrap(), 1103647397);
+        assert_eq!(o.encode_checked(15i64).unwrap(), 1103647397);
+        assert!(matches!(
+            o.encode_checked(MAX_INT + 1),
+            Err(OptimusError::InputOutOfRange {
+                value: 2147483648,
+                ..
+            })
+        ));
+        assert!(matches!(
+            o.encode_checked(-1i32),
+            Err(OptimusError::ConversionOverflow)
+        ));
+        assert!(matches!(
+            o.encode_checked(u128::MAX),
+            Err(OptimusError::ConversionOverflow)
+        ));
+    }
+    #[test]
+    fn test_resolve() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let expected = Resolution {
+            original: 15,
+            encoded: 1103647397,
+            in_range: true,
+        };
+        assert_eq!(o.resolve(1103647397).unwrap(), expected);
+        assert!(!o.resolve(MAX_INT + 1).unwrap().in_range);
+        for leaked in [1 << 40, u64::MAX] {
+            let resolution = o.resolve(leaked).unwrap();
+            assert!(!resolution.in_range);
+            assert_eq!(resolution.encoded, leaked);
+        }
+        let broken = Optimus::new_unchecked(1580030173, 59260788, 1163945558);
+        assert!(matches!(
+            broken.resolve(1103647397),
+            Err(OptimusError::InvalidModInverse { .. })
+        ));
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_string(&expected).unwrap(),
+            r#"{"original":15,"encoded":1103647397,"in_range":true}"#
+        );
+    }
+    #[test]
+    fn test_filter_valid() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert!(o.is_valid_encoded(0));
+        assert!(o.is_valid_encoded(MAX_INT));
+        assert!(!o.is_valid_encoded(MAX_INT + 1));
+        let tokens = [1103647397, u64::MAX, 0, MAX_INT + 1, MAX_INT];
+        let (valid, invalid) = o.filter_valid(&tokens);
+        assert_eq!(valid, [1103647397, 0, MAX_INT]);
+        assert_eq!(invalid, [u64::MAX, MAX_INT + 1]);
+    }
+    #[test]
+    fn test_hash() {
+        use std::collections::HashMap;
+        let a = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let b = Optimus::new(309779747, 49560203, 57733611).unwrap();
+        let mut cache = HashMap::new();
+        cache.insert(a, "a");
+        cache.insert(b, "b");
+        cache.insert(
+            Optimus::new_calculated(1580030173, 1163945558).unwrap(),
+            "a2",
+        );
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache[&a], "a2");
+        assert_ne!(a, a.with_random(1).unwrap());
+    }
+    #[test]
+    fn test_encode_low() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let shard = 5u64 << 27;
+        for row in [0, 1, 15, (1 << 27) - 1] {
+            let n = shard | row;
+            let e = o.encode_low(n, 4).unwrap();
+            assert_eq!(e >> 27, 5, "shard prefix must stay visible");
+            assert_eq!(o.decode_low(e, 4).unwrap(), n);
+        }
+        assert_eq!(o.encode_low(15, 0).unwrap(), o.encode(15));
+        assert_eq!(o.decode_low(MAX_INT, 23).unwrap() >> 8, MAX_INT >> 8);
+        assert!(matches!(
+            o.encode_low(15, 24),
+            Err(OptimusError::TooManyPreservedBits { bits: 24, max: 23 })
+        ));
+        assert!(matches!(
+            o.encode_low(MAX_INT + 1, 4),
+            Err(OptimusError::InputOutOfRange { .. })
+        ));
+        // Only the low bits of the product are kept, so the same config with all bits above
+        // MAX_INT set in prime and mod_inverse must give the same result without overflowing.
+        let big = Optimus::new_unchecked(1580030173 + !MAX_INT, 59260789 + !MAX_INT, 1163945558);
+        for n in [shard | 15, MAX_INT] {
+            let e = big.encode_low(n, 4).unwrap();
+            assert_eq!(e, o.encode_low(n, 4).unwrap());
+            assert_eq!(big.decode_low(e, 4).unwrap(), n);
+        }
+        let weak = Optimus::new(75161927681, 1, 0).unwrap();
+        assert_eq!(weak.encode_low(1 << 29, 0).unwrap(), 1 << 29);
+    }
+    #[test]
+    fn test_decode_detailed() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(
+            o.decode_detailed(1103647397),
+            DecodeResult {
+                id: 15,
+                input_in_range: true
+            }
+        );
+        let out_of_range = o.decode_detailed(MAX_INT + 1);
+        assert!(!out_of_range.input_in_range);
+        assert_eq!(out_of_range.id, o.decode(0));
+        let max = o.decode_detailed(u64::MAX);
+        assert!(!max.input_in_range);
+        assert_eq!(max.id, o.decode(MAX_INT));
+    }
+    #[test]
+    fn test_encode_timestamped() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let max_seconds = (1 << TIMESTAMP_BITS) - 1;
+        let max_seq = (1 << SEQUENCE_BITS) - 1;
+        for (seconds, seq) in [
+            (0, 0),
+            (86_400, 3),
+            (max_seconds, 0),
+            (0, max_seq),
+            (max_seconds, max_seq),
+        ] {
+            let e = o.encode_timestamped(seconds, seq).unwrap();
+            assert!(e <= MAX_INT);
+            assert_eq!(o.decode_timestamped(e), (seconds, seq));
+        }
+        assert!(matches!(
+            o.encode_timestamped(max_seconds + 1, 0),
+            Err(OptimusError::InputOutOfRange {
+                value: 16777216,
+                max: 16777215
+            })
+        ));
+        assert!(matches!(
+            o.encode_timestamped(0, max_seq + 1),
+            Err(OptimusError::InputOutOfRange {
+                value: 128,
+                max: 127
+            })
+        ));
+    }
+    #[test]
+    fn test_inverse() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let inv = o.inverse();
+        for n in [0, 1, 15, 1103647397, MAX_INT - 1, MAX_INT] {
+            assert_eq!(inv.encode(n), o.decode(n));
+            assert_eq!(inv.decode(n), o.encode(n));
+            assert_eq!(inv.decode(inv.encode(n)), n);
+            assert_eq!(inv.inverse().encode(n), o.encode(n));
+        }
+    }
 }
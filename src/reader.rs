@@ -0,0 +1,109 @@
+use std::io::Read;
+use std::str::FromStr;
+
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::optimus::Optimus;
+
+const FIELDS: [&str; 3] = ["prime", "mod_inverse", "random"];
+
+impl Optimus {
+    ///Returns an Optimus struct read from r, e.g. stdin or an inherited file descriptor, so
+    ///the config never has to appear on a command line where `ps` and shell history can
+    ///see it. The format is the same as `FromStr`: `prime:mod_inverse:random`, such as
+    ///`1580030173:59260789:1163945558`, optionally surrounded by whitespace.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Io` if r can not be read, or any error of `FromStr`
+    pub fn from_reader(mut r: impl Read) -> Result<Self, OptimusError> {
+        let mut contents = String::new();
+        r.read_to_string(&mut contents)?;
+        contents.parse()
+    }
+    ///Returns the config in the `prime:mod_inverse:random` format read by `FromStr` and
+    ///`from_reader`, for storing it in a secret manager.
+    ///
+    ///CAUTION: the string is the whole config, so treat it as a secret.
+    #[must_use]
+    pub fn to_secret_string(&self) -> String {
+        format!("{}:{}:{}", self.prime(), self.mod_inverse(), self.random())
+    }
+}
+
+impl FromStr for Optimus {
+    type Err = OptimusError;
+
+    ///Parses `prime:mod_inverse:random` and validates it the same way as `new`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(OptimusError::Parse(ParseErrorKind::EmptyInput));
+        }
+        let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+        if parts.len() > FIELDS.len() {
+            return Err(OptimusError::Parse(ParseErrorKind::TooManyFields {
+                expected: FIELDS.len(),
+            }));
+        }
+        let field = |i: usize| -> Result<u64, OptimusError> {
+            let part = parts.get(i).ok_or_else(|| {
+                OptimusError::Parse(ParseErrorKind::MissingField(FIELDS[i].to_string()))
+            })?;
+            part.parse()
+                .map_err(|_| OptimusError::Parse(ParseErrorKind::NotANumber(FIELDS[i].to_string())))
+        };
+        Self::new(field(0)?, field(1)?, field(2)?)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_from_reader() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let input = Cursor::new("1580030173:59260789:1163945558\n");
+        assert_eq!(Optimus::from_reader(input).unwrap(), o);
+        assert_eq!(
+            " 1580030173 : 59260789 : 1163945558 "
+                .parse::<Optimus>()
+                .unwrap(),
+            o
+        );
+    }
+
+    #[test]
+    fn test_to_secret_string() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        assert_eq!(o.to_secret_string(), "1580030173:59260789:1163945558");
+        assert_eq!(o.to_secret_string().parse::<Optimus>().unwrap(), o);
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        let kind = |s: &str| match s.parse::<Optimus>() {
+            Err(OptimusError::Parse(kind)) => kind,
+            other => panic!("expected a parse error for {s:?}, got {other:?}"),
+        };
+        assert_eq!(kind(" \n"), ParseErrorKind::EmptyInput);
+        assert_eq!(
+            kind("1580030173:59260789"),
+            ParseErrorKind::MissingField("random".to_string())
+        );
+        assert_eq!(
+            kind("1:2:3:4"),
+            ParseErrorKind::TooManyFields { expected: 3 }
+        );
+        assert_eq!(
+            kind("1580030173:x:1163945558"),
+            ParseErrorKind::NotANumber("mod_inverse".to_string())
+        );
+        assert!(matches!(
+            "1580030174:59260789:1163945558".parse::<Optimus>(),
+            Err(OptimusError::NotPrime)
+        ));
+    }
+}
@@ -0,0 +1,41 @@
+use futures_util::{Stream, StreamExt};
+
+use crate::optimus::Optimus;
+
+impl Optimus {
+    ///Returns a stream that encodes every item of s with a copy of this config. It is
+    ///`StreamExt::map` with `encode`, as encoding never waits on anything.
+    pub fn encode_stream<S: Stream<Item = u64>>(&self, s: S) -> impl Stream<Item = u64> {
+        s.map(self.encoder())
+    }
+    ///Returns a stream that decodes every item of s. See `encode_stream`.
+    pub fn decode_stream<S: Stream<Item = u64>>(&self, s: S) -> impl Stream<Item = u64> {
+        s.map(self.decoder())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use futures_util::{stream, FutureExt};
+
+    use super::*;
+
+    #[test]
+    fn test_stream() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let ids = vec![0, 15, Optimus::MAX_INT];
+        let encoded: Vec<u64> = o
+            .encode_stream(stream::iter(ids.clone()))
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(encoded[1], 1103647397);
+        let decoded: Vec<u64> = o
+            .decode_stream(stream::iter(encoded))
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(decoded, ids);
+    }
+}
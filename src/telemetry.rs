@@ -0,0 +1,121 @@
+//! Counters and a batch size histogram emitted through the `metrics` facade when the
+//! `metrics` feature is enabled. Only counts and sizes are ever recorded, never ids or
+//! config values. Without the feature the functions are empty and compile away.
+
+#[inline]
+pub(crate) fn record_encode() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("optimus_encode_total").increment(1);
+}
+
+#[inline]
+pub(crate) fn record_decode() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("optimus_decode_total").increment(1);
+}
+
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn record_batch(len: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!("optimus_batch_size").record(len as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = len;
+}
+
+#[cfg(all(test, feature = "metrics"))]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use crate::Optimus;
+
+    #[test]
+    fn test_counters() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            let _ = o.decode(o.encode(15));
+            let _ = o.encode(16);
+        });
+        let counters: Vec<(String, DebugValue)> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| (key.key().name().to_string(), value))
+            .collect();
+        assert!(counters.contains(&("optimus_encode_total".to_string(), DebugValue::Counter(2))));
+        assert!(counters.contains(&("optimus_decode_total".to_string(), DebugValue::Counter(1))));
+    }
+
+    /// Cycle-walking takes several encode steps for some ids, but each call counts once.
+    #[test]
+    fn test_partitioned_counts_once() {
+        use crate::optimus::MAX_RESERVED;
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            for n in MAX_RESERVED..MAX_RESERVED + 100 {
+                let e = o.encode_partitioned(n, MAX_RESERVED).unwrap();
+                let _ = o.decode_partitioned(e, MAX_RESERVED).unwrap();
+            }
+        });
+        let counters: Vec<(String, DebugValue)> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| (key.key().name().to_string(), value))
+            .collect();
+        assert!(counters.contains(&("optimus_encode_total".to_string(), DebugValue::Counter(100))));
+        assert!(counters.contains(&("optimus_decode_total".to_string(), DebugValue::Counter(100))));
+    }
+
+    /// Helpers that compare or audit configs encode many samples of their own, but only
+    /// the public encode and decode calls count, once each.
+    #[test]
+    fn test_counts_once_per_call() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let legacy = Optimus::new(309779747, 49560203, 57733611).unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            let _ = o.encode(15);
+            let _ = o.decode(1103647397);
+            let _ = o.decode_with_fallback(1103647397, &legacy, |_| false);
+            let _ = o.encodes_same_as(&legacy, 100);
+            let _ = o.is_identity_like();
+            let _ = Optimus::migration_check(&legacy, &o, &[(1, 2), (3, 4)]);
+            let _ = Optimus::output_collisions(&[o, legacy], 15);
+            let _ = o.inputs_mapping_to(0..10).count();
+        });
+        let counters: Vec<(String, DebugValue)> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| (key.key().name().to_string(), value))
+            .collect();
+        assert!(counters.contains(&("optimus_encode_total".to_string(), DebugValue::Counter(1))));
+        assert!(counters.contains(&("optimus_decode_total".to_string(), DebugValue::Counter(2))));
+    }
+
+    #[test]
+    fn test_batch_histogram() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            o.encode_base62_into(&[1, 2, 3], &mut String::new());
+        });
+        let histogram = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .find(|(key, _, _, _)| key.key().name() == "optimus_batch_size")
+            .map(|(_, _, _, value)| value);
+        assert!(
+            matches!(histogram, Some(DebugValue::Histogram(v)) if v.len() == 1 && (v[0].0 - 3.0).abs() < f64::EPSILON)
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Integrations for fuzzing and property testing code that consumes an `Optimus`. Every
+//! generated config comes from `Optimus::from_seed`, so it always satisfies `validate()`.
+
+use crate::optimus::Optimus;
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Optimus {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::from_seed(u.arbitrary()?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        <u64 as arbitrary::Arbitrary>::size_hint(depth)
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Optimus {
+    type Parameters = ();
+    type Strategy = proptest::strategy::Map<proptest::num::u64::Any, fn(u64) -> Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        proptest::strategy::Strategy::prop_map(proptest::num::u64::ANY, Self::from_seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        use crate::optimus::Optimus;
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut u = Unstructured::new(&bytes);
+        for _ in 0..16 {
+            let o = Optimus::arbitrary(&mut u).unwrap();
+            assert!(o.validate().is_ok(), "{o:?}");
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn test_proptest_arbitrary(o in proptest::prelude::any::<crate::optimus::Optimus>(), n in 0..=crate::optimus::MAX_INT) {
+            proptest::prop_assert!(o.validate().is_ok());
+            proptest::prop_assert_eq!(o.decode(o.encode(n)), n);
+        }
+    }
+}
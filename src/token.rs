@@ -0,0 +1,98 @@
+//! Versioned string tokens.
+//!
+//! A token is a single version character followed by the encoded id in the format of that
+//! version. Tagging tokens lets a later change of algorithm, width or checksum introduce a
+//! new version while tokens handed out before it keep decoding.
+
+use crate::error::{OptimusError, ParseErrorKind};
+use crate::optimus::Optimus;
+
+///Version tag written by `encode_token`: the id is `encode_base62` of it.
+pub const TOKEN_V1: char = '1';
+
+type TokenDecoder = fn(&Optimus, &str) -> Result<u64, OptimusError>;
+
+///The token versions `decode_token` understands.
+const DECODERS: &[(char, TokenDecoder)] = &[(TOKEN_V1, Optimus::decode_base62)];
+
+impl Optimus {
+    ///Encodes n and returns it as a token of the current version, `TOKEN_V1` followed by
+    ///`encode_base62(n)`, so at most 7 characters.
+    #[must_use]
+    pub fn encode_token(&self, n: u64) -> String {
+        let mut token = String::from(TOKEN_V1);
+        token.push_str(&self.encode_base62(n));
+        token
+    }
+    ///Decodes a token produced by `encode_token`, using the decoder for the version tag it
+    ///starts with.
+    /// # Errors
+    ///
+    /// Will return `OptimusError::Parse` with `ParseErrorKind::EmptyInput` if the token is
+    /// empty,
+    /// `OptimusError::UnknownVersion` if its version tag is not recognised, or any error of
+    /// that version's decoder
+    pub fn decode_token(&self, token: &str) -> Result<u64, OptimusError> {
+        self.decode_token_with(token, DECODERS)
+    }
+
+    fn decode_token_with(
+        &self,
+        token: &str,
+        decoders: &[(char, TokenDecoder)],
+    ) -> Result<u64, OptimusError> {
+        let mut chars = token.chars();
+        let version = chars
+            .next()
+            .ok_or(OptimusError::Parse(ParseErrorKind::EmptyInput))?;
+        let (_, decoder) = decoders
+            .iter()
+            .find(|(v, _)| *v == version)
+            .ok_or(OptimusError::UnknownVersion(version))?;
+        decoder(self, chars.as_str())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        for id in [0, 15, Optimus::MAX_INT] {
+            let token = o.encode_token(id);
+            assert!(token.starts_with(TOKEN_V1));
+            assert_eq!(&token[1..], o.encode_base62(id));
+            assert_eq!(o.decode_token(&token).unwrap(), id);
+        }
+        assert!(matches!(
+            o.decode_token(""),
+            Err(OptimusError::Parse(ParseErrorKind::EmptyInput))
+        ));
+        assert!(matches!(
+            o.decode_token(TOKEN_V1.to_string().as_str()),
+            Err(OptimusError::Parse(ParseErrorKind::EmptyInput))
+        ));
+    }
+
+    /// A decoder that only knows a hypothetical v2 rejects v1 tokens, and the current
+    /// decoder rejects v2 tokens, instead of misreading either.
+    #[test]
+    fn test_unknown_version() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        let v2_only: &[(char, TokenDecoder)] = &[('2', Optimus::decode_base62)];
+        let v1_token = o.encode_token(15);
+        assert!(matches!(
+            o.decode_token_with(&v1_token, v2_only),
+            Err(OptimusError::UnknownVersion('1'))
+        ));
+        let v2_token = format!("2{}", o.encode_base62(15));
+        assert_eq!(o.decode_token_with(&v2_token, v2_only).unwrap(), 15);
+        assert!(matches!(
+            o.decode_token(&v2_token),
+            Err(OptimusError::UnknownVersion('2'))
+        ));
+    }
+}
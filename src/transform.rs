@@ -0,0 +1,110 @@
+//! A trait for reversible id transforms, so the string and batch helpers can be reused
+//! with schemes other than the default multiply and xor of `Optimus`.
+//!
+//! ```
+//! use optimus::transform::Transform;
+//! use optimus::Optimus;
+//!
+//! fn render(t: &impl Transform, ids: &[u64]) -> String {
+//!     let mut out = String::new();
+//!     t.encode_base62_into(ids, &mut out);
+//!     out
+//! }
+//! let opt = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+//! assert_eq!(opt.decode_base62_slice(&render(&opt, &[1, 2])).unwrap(), [1, 2]);
+//! ```
+
+use crate::base62;
+use crate::core::RawOptimus;
+use crate::error::OptimusError;
+use crate::optimus::{Inverse, Optimus};
+
+///A bijection over the id space `0..=MAX_INT`. Implementations must satisfy
+///`decode(encode(n)) == n` for every n in the id space, and encode must never return a
+///value above `MAX_INT`, or the string helpers will not round trip.
+pub trait Transform {
+    ///Encodes n.
+    fn encode(&self, n: u64) -> u64;
+    ///Decodes a value returned by `encode`.
+    fn decode(&self, n: u64) -> u64;
+
+    ///Encodes n and returns it as a base62 string. See `Optimus::encode_base62`.
+    fn encode_base62(&self, n: u64) -> String {
+        base62::encode_base62(self, n)
+    }
+    ///Decodes a string produced by `encode_base62`. See `Optimus::decode_base62`.
+    /// # Errors
+    ///
+    /// Will return the errors documented on `Optimus::decode_base62`
+    fn decode_base62(&self, s: &str) -> Result<u64, OptimusError> {
+        base62::decode_base62(self, s)
+    }
+    ///Appends the base62 string of every id to out. See `Optimus::encode_base62_into`.
+    fn encode_base62_into(&self, ids: &[u64], out: &mut String) {
+        base62::encode_base62_into(self, ids, out);
+    }
+    ///Decodes a list written by `encode_base62_into`. See `Optimus::decode_base62_slice`.
+    /// # Errors
+    ///
+    /// Will return the errors documented on `Optimus::decode_base62_slice`
+    fn decode_base62_slice(&self, s: &str) -> Result<Vec<u64>, OptimusError> {
+        base62::decode_base62_slice(self, s)
+    }
+}
+
+impl Transform for Optimus {
+    fn encode(&self, n: u64) -> u64 {
+        Optimus::encode(self, n)
+    }
+
+    fn decode(&self, n: u64) -> u64 {
+        Optimus::decode(self, n)
+    }
+}
+
+impl Transform for RawOptimus {
+    fn encode(&self, n: u64) -> u64 {
+        RawOptimus::encode(self, n)
+    }
+
+    fn decode(&self, n: u64) -> u64 {
+        RawOptimus::decode(self, n)
+    }
+}
+
+impl Transform for Inverse {
+    fn encode(&self, n: u64) -> u64 {
+        Inverse::encode(self, n)
+    }
+
+    fn decode(&self, n: u64) -> u64 {
+        Inverse::decode(self, n)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+    use crate::optimus::MAX_INT;
+
+    fn round_trip(t: &dyn Transform) {
+        let ids = [0, 1, 15, MAX_INT];
+        let mut out = String::new();
+        t.encode_base62_into(&ids, &mut out);
+        assert_eq!(t.decode_base62_slice(&out).unwrap(), ids);
+        for id in ids {
+            assert_eq!(t.decode_base62(&t.encode_base62(id)).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_transforms() {
+        let o = Optimus::new(1580030173, 59260789, 1163945558).unwrap();
+        round_trip(&o);
+        round_trip(&o.raw());
+        round_trip(&o.inverse());
+        assert_eq!(Transform::encode_base62(&o, 15), o.encode_base62(15));
+        assert_ne!(o.inverse().encode_base62(15), o.encode_base62(15));
+    }
+}